@@ -4,7 +4,6 @@
 /// can prove his/her key pair is legit and tied to the digital certificate of Certificate card.
 /// This module works together with governance module, to permit users to vote only one time, allowing the implementation of more secure voting method.
 
-// use crate::rsa::*;
 use rstd::prelude::*;
 use support::{dispatch::Result, decl_storage, decl_module, decl_event, ensure, print};
 use system::{ensure_signed};
@@ -12,6 +11,10 @@ use codec::{Encode, Decode};
 // use sr_primitives::traits::{};
 // use primitives::{};
 
+// layout of the storage of this module.
+// 1: first versioned layout, Certification { cert, signature }. Earlier chains have the same layout
+pub const STORAGE_VERSION: u32 = 1;
+
 pub trait Trait: system::Trait {
     type Event: From<Event<Self>> + Into<<Self as system::Trait>::Event>;
 }
@@ -54,6 +57,9 @@ decl_storage! {
 
         // Raw Certificate in hex
         CAStore get(ca_store): map u64 => Vec<u8>;
+
+        // 0 is the layout before versioning
+        StorageVersion get(storage_version) build(|_: &GenesisConfig| STORAGE_VERSION): u32;
     }
 }
 
@@ -61,6 +67,12 @@ decl_module! {
     pub struct Module<T: Trait> for enum Call where origin: T::Origin {
         fn deposit_event() = default;
 
+        fn on_initialize(_n: T::BlockNumber) {
+            if Self::storage_version() < STORAGE_VERSION {
+                Self::on_runtime_upgrade();
+            }
+        }
+
         // register new ca. Takes CAHash
         // checks:
         //  - Hash doesn't exist
@@ -159,6 +171,12 @@ decl_module! {
 
 
 impl<T: Trait> Module<T> {
+    // brings storage written by an older runtime up to STORAGE_VERSION, one version at a time
+    fn on_runtime_upgrade() {
+        // 0 -> 1: nothing to re-encode
+        <StorageVersion>::put(STORAGE_VERSION);
+    }
+
     // helper function to check if the account is registered.
     // TODO: verify certification. Now only checks runtime storage.
     pub fn check_account(sender:T::AccountId, cahash:T::Hash) -> Result {
@@ -173,13 +191,12 @@ impl<T: Trait> Module<T> {
 #[cfg(test)]
 mod tests {
     use super::*;
-    // use crate::rsa::*;
     use support::{
         impl_outer_origin, assert_ok, assert_noop, parameter_types
     };
     use runtime_io::{TestExternalities};
     use sr_primitives::{
-        Perbill, traits::{IdentityLookup, Hash, Member, },
+        Perbill, traits::{IdentityLookup, Hash, Member, OnInitialize},
         testing::{Header}
     };
     use primitives::{H256};
@@ -238,7 +255,7 @@ mod tests {
         assert_eq!(Certificate::cahash_count(), 0);
 
         // register new ca
-        assert_ok!(Certificate::register_ca(Origin::signed(1), CAHash, data.clone()));
+        assert_ok!(Certificate::register_ca(Origin::signed(1), CAHash, data.clone(), data.clone()));
         
         // respective storage changes
         assert_eq!(Certificate::cahash_count(), 1);
@@ -246,7 +263,7 @@ mod tests {
         assert_eq!(Certificate::index_by_cahash(CAHash), 1);
 
         // cannot register same CAhash
-        assert_noop!(Certificate::register_ca(Origin::signed(1), CAHash, data), "Provided CAHash is already registered.");
+        assert_noop!(Certificate::register_ca(Origin::signed(1), CAHash, data.clone(), data), "Provided CAHash is already registered.");
       });
     }
 
@@ -264,7 +281,7 @@ mod tests {
 
         // cannot register for non-existing CA
         assert_noop!(Certificate::register_account(Origin::signed(1), CAHash, cert, signature), "Provided CAHash doesn't exist.");
-        assert_ok!(Certificate::register_ca(Origin::signed(1), CAHash, data));
+        assert_ok!(Certificate::register_ca(Origin::signed(1), CAHash, data.clone(), data));
         assert_ok!(Certificate::register_account(Origin::signed(1), CAHash, cert, signature));
 
         // respective storage changes
@@ -279,6 +296,30 @@ mod tests {
         
       });
     }
+    #[test]
+    fn migrates_unversioned_storage() {
+      let mut t = system::GenesisConfig::default().build_storage::<Test>().unwrap();
+      GenesisConfig::default().assimilate_storage::<Test>(&mut t).unwrap();
+      TestExternalities::from(t).execute_with(||{
+        // new chains start at the current layout
+        assert_eq!(Certificate::storage_version(), STORAGE_VERSION);
+      });
+
+      TestExternalities::default().execute_with(||{
+        let CAHash = sr_primitives::traits::BlakeTwo256::hash(&[111, 112, 113, 114]);
+        let cert = sr_primitives::traits::BlakeTwo256::hash(&[221, 222, 223, 224]);
+        let signature = sr_primitives::traits::BlakeTwo256::hash(&[11, 12, 13, 14]);
+        // a Certification written before versioning
+        let key = <CertificateStore<Test>>::hashed_key_for((1, CAHash));
+        support::storage::unhashed::put_raw(&key, &[cert.as_bytes(), signature.as_bytes()].concat());
+        assert_eq!(Certificate::storage_version(), 0);
+
+        Certificate::on_initialize(1);
+        assert_eq!(Certificate::storage_version(), STORAGE_VERSION);
+        assert_eq!(Certificate::certificate_store((1, CAHash)), Certification { cert, signature });
+      });
+    }
+
     // fn can_register() {
     //     TestExternalities::default().execute_with(||{
     //         // register account
@@ -1,15 +1,26 @@
 use crate::certificate;
 
 use support::{
-    decl_module, decl_storage, decl_event, dispatch::Result, ensure, print,
+    decl_module, decl_storage, decl_event, dispatch::Result, ensure, print, Parameter,
     traits::{
         LockableCurrency, WithdrawReason, WithdrawReasons, LockIdentifier, Currency,
+        ExistenceRequirement, Get, ReservableCurrency, OnUnbalanced, EnsureOrigin,
     }
 };
-use system::ensure_signed;
+use system::{ensure_signed, ensure_root};
+use rstd::marker::PhantomData;
 use codec::{Encode, Decode};
-use rstd::prelude::Vec;
-use sr_primitives::traits::{CheckedAdd, SaturatedConversion};
+use rstd::prelude::{Vec, Box};
+use sr_primitives::Perbill;
+use sr_primitives::weights::{Weight, WeighData, ClassifyDispatch, DispatchClass, GetDispatchInfo};
+use sr_primitives::traits::{
+    CheckedAdd, SaturatedConversion, Saturating, IntegerSquareRoot, Zero, One, Dispatchable, Hash as HashT,
+};
+use tally::{Tally, TallyStrategy, VoteWeight, RunoffResult, RunoffRound, Threshold, Outcome};
+use elgamal::{Ciphertext, BallotProof, DecryptionProof, Point};
+pub mod tally;
+pub mod elgamal;
+mod migration;
 mod tests;
 
 // Option: {title: String, pot: u64, voters: <Vec:T::AccountId>}
@@ -19,12 +30,160 @@ mod tests;
 #[cfg_attr(feature = "std", derive(Debug))]
 pub struct Vote<AccountId, BlockNumber, Hash> {
     id: u64,
-    vote_type: u8,
+    vote_type: VoteType,
     approved: Hash, // Index for required certificate. 0 means no certificate is required. 
     creator: AccountId,
     when: BlockNumber,
+    starts_at: BlockNumber, // first block accepting ballots
     vote_ends: BlockNumber,
     concluded: bool,
+    quorum: u64, // minimum number of voters
+    threshold: Threshold,
+}
+
+// VoteType decides which cast_* function accepts ballots and how the vote is tallied.
+// Variants are encoded as 0, 1, 2, same as the former vote_type: u8.
+#[derive(Clone, Copy, PartialEq, Eq, Encode, Decode)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub enum VoteType {
+    Simple,      // Aye/Nay/Abstain via cast_ballot. 1 account 1 vote
+    Lock,        // Aye/Nay/Abstain via cast_lockvote. deposit * duration
+    MultiOption, // one of VoteOptions via cast_ballot_with_options
+    Quadratic(CreditPolicy), // one of VoteOptions via cast_quadratic_vote. square root of spent credits
+    RankedChoice, // ranking of VoteOptions via cast_ranked_ballot. instant-runoff
+    Approval,     // any subset of VoteOptions via cast_approval_ballot. 1 count for each approved option
+    Score(u8),    // score from 0 to the given maximum for each of VoteOptions via cast_score_ballot. sum of scores
+    CommitReveal, // Aye/Nay/Abstain committed as hash via commit_ballot, opened via reveal_ballot after vote_ends
+    Encrypted,    // Aye/Nay encrypted to trustees via cast_encrypted_ballot. only the sum is decrypted
+}
+
+// A voter's ballot, kept in Ballots
+#[derive(Clone, PartialEq, Eq, Encode, Decode)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub enum OptionBallot {
+    Binary(Ballot),    // Simple, Lock and CommitReveal
+    Single(u8),        // MultiOption and Quadratic
+    Ranked(Vec<u8>),   // options from most to least preferred
+    Approval(Vec<u8>), // approved options
+    Score(Vec<u8>),    // score of each option, in the order of VoteOptions
+}
+
+// Latest ballot of a voter and the weight it was cast with.
+// weight is 1, deposit * multiplier for Lock and the square root of the credits for Quadratic
+#[derive(Clone, PartialEq, Eq, Encode, Decode)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct BallotRecord {
+    pub ballot: OptionBallot,
+    pub weight: VoteWeight,
+}
+
+// What happens to the credits spent on a quadratic ballot
+#[derive(Clone, Copy, PartialEq, Eq, Encode, Decode)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub enum CreditPolicy {
+    Lock, // locked like a lockvote until the vote ends. use withdraw afterwards
+    Burn, // removed from the voter's balance and total issuance
+}
+
+// Settings of create_vote besides the vote_type, its length, data, certificate and options.
+// The default has no quorum, a simple majority threshold, no proposal, opens at once and holds locks until LockInfo.until
+#[derive(Clone, PartialEq, Eq, Encode, Decode)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct VoteSettings<Proposal, BlockNumber> {
+    pub quorum: u64,          // minimum number of voters
+    pub threshold: Threshold, // share of counted weight the leading choice needs to pass
+    // call dispatched with RawOrigin::Passed if the vote passes.
+    // needs a certificate, a quorum of at least ProposalQuorum and a weight of at most MaxProposalWeight
+    pub proposal: Option<Box<Proposal>>,
+    pub starts_at: Option<BlockNumber>, // block ballots open at, for a notice period. None opens the vote now
+    pub early_unlock: Option<EarlyUnlock>, // lets voters of a Lock vote withdraw early for a penalty
+}
+
+impl<Proposal, BlockNumber> Default for VoteSettings<Proposal, BlockNumber> {
+    fn default() -> Self {
+        VoteSettings {
+            quorum: 0,
+            threshold: Threshold::SimpleMajority,
+            proposal: None,
+            starts_at: None,
+            early_unlock: None,
+        }
+    }
+}
+
+// Lets voters withdraw before LockInfo.until once the vote concluded, for a penalty.
+// Chosen by the creator of a Lock vote. credit locks of Quadratic(Lock) votes end with the vote anyway
+#[derive(Clone, Copy, PartialEq, Eq, Encode, Decode)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct EarlyUnlock {
+    pub penalty: Perbill, // share of the deposit slashed
+    pub destination: PenaltyDestination,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Encode, Decode)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub enum PenaltyDestination {
+    Treasury, // handed to Trait::Treasury
+    Burn,     // removed from total issuance
+}
+
+// What reap_vote keeps of the ballots of a vote.
+// ballot_root: Merkle root over the hash of (voter, BallotRecord), or (voter, Ciphertext) for Encrypted votes,
+// in the order of Voters
+#[derive(Clone, PartialEq, Eq, Encode, Decode)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct VoteArchive<Hash> {
+    pub ballot_root: Hash,
+    pub ballots: u64,
+}
+
+// Result of a concluded vote, kept in VoteResults
+#[derive(Clone, PartialEq, Eq, Encode, Decode, Default)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct VoteResult<BlockNumber> {
+    pub tally: Tally,          // weight counted for each option
+    pub head_counts: Vec<u64>, // accounts counted for each option, delegators included
+    pub ballots: u64,          // ballots cast directly
+    pub turnout: u64,          // accounts that cast a ballot, directly or through a delegate
+    pub eligible: Option<u64>, // accounts holding the certificate of the vote. None if no certificate is required
+    pub concluded_at: BlockNumber,
+}
+
+impl Default for VoteType {
+    fn default() -> Self {
+        VoteType::Simple
+    }
+}
+
+// Stage of the counting of a delegable vote. Each walks a list of accounts from Counting.next
+#[derive(Clone, Copy, PartialEq, Eq, Encode, Decode)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub enum CountStage {
+    Committers,     // unrevealed commitments of CommitReveal votes are slashed
+    VoteDelegators, // delegators for the vote count for the ballot their power reaches
+    Delegators,     // so do delegators for every vote, unless they delegated for the vote too
+    Runoff,         // RankedChoice votes pass over their voters once per instant-runoff round
+    Done,
+}
+
+impl Default for CountStage {
+    fn default() -> Self {
+        CountStage::Committers
+    }
+}
+
+// Counting of a delegable vote so far, kept in CountProgress while the vote is counted over several blocks.
+// totals, head_counts and turnout start from the ballots cast directly. in the Runoff stage,
+// totals are those of the current round
+#[derive(Clone, PartialEq, Eq, Encode, Decode, Default)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct Counting {
+    pub stage: CountStage,
+    pub next: u64,
+    pub totals: Vec<VoteWeight>,
+    pub head_counts: Vec<u64>,
+    pub turnout: u64,
+    pub rounds: Vec<RunoffRound>, // closed rounds of a RankedChoice vote
 }
 
 #[derive(Clone, Copy, PartialEq, Eq, Encode, Decode)]
@@ -32,6 +191,21 @@ pub struct Vote<AccountId, BlockNumber, Hash> {
 pub enum Ballot {
     Aye,
     Nay,
+    Abstain, // counts toward quorum, not toward approval
+}
+
+impl Ballot {
+    // every ballot of a simple vote, in the order of their slots
+    pub const ALL: [Ballot; 3] = [Ballot::Aye, Ballot::Nay, Ballot::Abstain];
+
+    // option of the ballot in OptionTotals and Tally
+    pub fn slot(self) -> u8 {
+        match self {
+            Ballot::Aye => 0,
+            Ballot::Nay => 1,
+            Ballot::Abstain => 2,
+        }
+    }
 }
 
 #[derive(PartialEq, Encode, Decode, Default)]
@@ -43,21 +217,143 @@ pub struct LockInfo<Balance, BlockNumber> {
 }
 
 pub type ReferenceIndex = u64;
+// single lock of an account over the deposits of all its lockvotes and locked credits
+const GOVERNANCE_LOCK: LockIdentifier = *b"mgovernc";
+// votes returned by a page of votes_by_creator
+pub const VOTES_PER_PAGE: u64 = 20;
+// options are indexed by u8
+pub const MAX_OPTIONS: usize = 254;
+// weight of counting a vote: a base, a share for each option, and a share for each account visited:
+// committers, each delegator with the delegates its power passes on the way to a voter,
+// and the voters of Encrypted votes, as their decrypted sum is searched up to their number
+pub const CONCLUDE_BASE_WEIGHT: Weight = 10_000;
+pub const CONCLUDE_OPTION_WEIGHT: Weight = 100;
+pub const CONCLUDE_ACCOUNT_WEIGHT: Weight = 50;
+// weight of cancelling a vote: a base, and a share for each voter or committer released
+pub const CANCEL_BASE_WEIGHT: Weight = 10_000;
+pub const CANCEL_ACCOUNT_WEIGHT: Weight = 200;
+// weight of reaping a vote: a base, and a share for each voter or delegation for the vote cleared
+pub const REAP_BASE_WEIGHT: Weight = 10_000;
+pub const REAP_ACCOUNT_WEIGHT: Weight = 200;
+// Origin of the proposal of a vote, dispatched when the vote concludes as Passed
+#[derive(PartialEq, Eq, Clone)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub enum RawOrigin {
+    Passed(ReferenceIndex),
+}
+
+pub type Origin = RawOrigin;
+
+// Ensures the origin is the proposal of a passed vote. Success is the reference_index of the vote
+pub struct EnsurePassed;
+
+impl<O: Into<rstd::result::Result<RawOrigin, O>> + From<RawOrigin>> EnsureOrigin<O> for EnsurePassed {
+    type Success = ReferenceIndex;
+    fn try_origin(o: O) -> rstd::result::Result<Self::Success, O> {
+        o.into().map(|RawOrigin::Passed(reference_index)| reference_index)
+    }
+}
+
 pub type BalanceOf<T> = <<T as Trait>::Currency as Currency<<T as system::Trait>::AccountId>>::Balance;
+pub type NegativeImbalanceOf<T> = <<T as Trait>::Currency as Currency<<T as system::Trait>::AccountId>>::NegativeImbalance;
+type VoteOf<T> = Vote<<T as system::Trait>::AccountId, <T as system::Trait>::BlockNumber, <T as system::Trait>::Hash>;
+pub type VoteSettingsOf<T> = VoteSettings<<T as Trait>::Proposal, <T as system::Trait>::BlockNumber>;
 
 // import Trait from balances, timestamp, event
 pub trait Trait: certificate::Trait + balances::Trait + system::Trait {
     type Event: From<Event<Self>> + Into<<Self as system::Trait>::Event>;
-    type Currency: LockableCurrency<Self::AccountId, Moment=Self::BlockNumber>;
+    type Currency: LockableCurrency<Self::AccountId, Moment=Self::BlockNumber> + ReservableCurrency<Self::AccountId>;
+    // counting method for Simple, CommitReveal, MultiOption and Approval votes
+    type SimpleTally: TallyStrategy;
+    // counting method for Lock, Quadratic and Score votes
+    type LockTally: TallyStrategy;
+    // weight of the votes counted in on_finalize of a block at most. the rest rolls to the next block.
+    // votes whose smallest step of counting weighs more than this are left to conclude_vote
+    type MaxConclusionWeight: Get<Weight>;
+    // origin proposals are dispatched with: the runtime Origin, which includes RawOrigin
+    type ProposalOrigin: From<RawOrigin>;
+    // call attached to a vote, dispatched with RawOrigin::Passed when the vote passes
+    type Proposal: Parameter + Dispatchable<Origin=Self::ProposalOrigin> + GetDispatchInfo;
+    // minimum quorum of a vote with a proposal
+    type ProposalQuorum: Get<u64>;
+    // weight of a proposal at most. the calls that may conclude a vote are charged for it
+    type MaxProposalWeight: Get<Weight>;
+    // blocks after vote_ends in which CommitReveal ballots can be revealed
+    type RevealPeriod: Get<Self::BlockNumber>;
+    // reserved on commit_ballot, returned on reveal_ballot and slashed if never revealed
+    type RevealDeposit: Get<BalanceOf<Self>>;
+    // receives slashed deposits
+    type Slash: OnUnbalanced<NegativeImbalanceOf<Self>>;
+    // reserved from the creator on create_vote: base + per byte * bytes the vote stores
+    type CreationDepositBase: Get<BalanceOf<Self>>;
+    type CreationDepositPerByte: Get<BalanceOf<Self>>;
+    // origin that can flag a vote as spam, slashing its creation deposit
+    type SpamOrigin: EnsureOrigin<Self::Origin>;
+    // origin that can cancel any vote at any time, besides root
+    type CancelOrigin: EnsureOrigin<Self::Origin>;
+    // base period of the conviction table
+    type LockPeriod: Get<Self::BlockNumber>;
+    // (periods, multiplier) in ascending order of periods. A lockvote lasting `periods` LockPeriods past
+    // vote_ends weighs deposit * multiplier. Locks cannot last past the longest entry.
+    type Convictions: Get<&'static [(u32, u32)]>;
+    // receives early withdrawal penalties of votes with PenaltyDestination::Treasury
+    type Treasury: OnUnbalanced<NegativeImbalanceOf<Self>>;
+    // blocks after ballots close during which a concluded vote keeps its per-voter data
+    type RetentionPeriod: Get<Self::BlockNumber>;
+    // share of the creation deposit kept reserved until reap_vote pays it to the caller
+    type ReapReward: Get<BalanceOf<Self>>;
+    // delegations the power of a delegator follows at most to reach a voter. longer chains count for no one
+    type MaxDelegationDepth: Get<u32>;
 }
 
 decl_event!(
-	pub enum Event<T> where AccountId = <T as system::Trait>::AccountId {
+	pub enum Event<T> where AccountId = <T as system::Trait>::AccountId, Balance = BalanceOf<T>, BlockNumber = <T as system::Trait>::BlockNumber {
         //created, voted, withdrawn, finalized
         Created(AccountId, u64),
+        // the vote accepts ballots from this block
+        Opened(ReferenceIndex),
+        // creation deposit returned to the creator, or slashed for failing quorum or spam
+        DepositReturned(AccountId, ReferenceIndex, Balance),
+        DepositSlashed(AccountId, ReferenceIndex, Balance),
+        Cancelled(ReferenceIndex),
+        // vote, voters and committers of the cancelled vote left to release
+        CancelPending(ReferenceIndex, u64),
+        // caller, vote, reward paid from the creation deposit
+        Reaped(AccountId, ReferenceIndex, Balance),
+        // vote, voters and delegations for the vote left to reap
+        ReapPending(ReferenceIndex, u64),
         Voted(AccountId, u64, Ballot),
-        Concluded(u64),
+        // voter, vote, ballot, deposit, multiplier, weight = deposit * multiplier,
+        // with the multiplier of the Convictions entry for the full LockPeriods the lock lasts past vote_ends
+        LockVoted(AccountId, ReferenceIndex, Ballot, Balance, u32, Balance),
+        // voter, vote, deposit, until, multiplier and weight after a top-up or extension, same formula as LockVoted
+        LockExtended(AccountId, ReferenceIndex, Balance, BlockNumber, u32, Balance),
+        // voter, vote, penalty slashed from the deposit
+        WithdrewEarly(AccountId, ReferenceIndex, Balance),
+        // voter, reference_index, option, spent credits
+        QuadraticVoted(AccountId, ReferenceIndex, u8, Balance),
+        // voter, cancelled vote, credits burned on it
+        CreditsForfeited(AccountId, ReferenceIndex, Balance),
+        // voter, reference_index, ballot over VoteOptions
+        OptionVoted(AccountId, ReferenceIndex, OptionBallot),
+        // per-option result and outcome of the vote
+        Concluded(ReferenceIndex, Tally, Outcome),
+        // weighs more than MaxConclusionWeight, left to conclude_vote
+        ConclusionDeferred(ReferenceIndex),
+        // counted in part, continued in the next block or by conclude_vote
+        CountPending(ReferenceIndex),
         Withdrew(AccountId, ReferenceIndex),
+        // proposal of a passed vote was dispatched. true if it succeeded
+        Executed(ReferenceIndex, bool),
+        // delegator, delegate, vote it applies to. None for every vote
+        Delegated(AccountId, AccountId, Option<ReferenceIndex>),
+        Undelegated(AccountId, Option<ReferenceIndex>),
+        Committed(AccountId, ReferenceIndex),
+        // commitment not revealed before the vote concluded. its deposit is slashed
+        Unrevealed(AccountId, ReferenceIndex, Balance),
+        TrusteesSet(ReferenceIndex, Vec<AccountId>),
+        EncryptedVoted(AccountId, ReferenceIndex),
+        DecryptionShareSubmitted(AccountId, ReferenceIndex),
 	}
 );
 
@@ -75,43 +371,185 @@ decl_storage! {
         VoteCreator get(creator_of): map u64 => Option<T::AccountId>;
         CreatedVoteCount get(created_by): map T::AccountId => u64; // increment everytime created
 
-        // VoteByCreatorArray get(created_by): map T::AccountId => <Vec: u64>;
-        VoteByCreatorArray get(created_by_and_index): map (T::AccountId, u64) => Vote<T::AccountId, T::BlockNumber, T::Hash>;
+        // creator and n => the creator's nth vote, from 1 to CreatedVoteCount. VotesByIndex holds the vote
+        VoteByCreatorArray get(created_by_and_index): map (T::AccountId, u64) => Option<ReferenceIndex>;
+        // layout of the storage of this module, see migration. 0 is the layout before versioning
+        StorageVersion get(storage_version) build(|_: &GenesisConfig| migration::STORAGE_VERSION): u32;
 
-        VoteResults: map u64 => Vec<u64>;
+        VoteResults get(result_of): map ReferenceIndex => VoteResult<T::BlockNumber>;
+        VoteOutcomes get(outcome_of): map ReferenceIndex => Option<Outcome>;
+        // dispatched when the vote concludes as Passed
+        Proposals get(proposal_of): map ReferenceIndex => Option<T::Proposal>;
+        // early withdrawal policy of the vote. None means locks are held until LockInfo.until
+        EarlyUnlocks get(early_unlock_of): map ReferenceIndex => Option<EarlyUnlock>;
+        // votes concluded automatically in on_finalize of the block
+        VoteExpiry get(expiring_at): map T::BlockNumber => Vec<ReferenceIndex>;
+        // creator and deposit reserved by create_vote, until the vote concludes or is flagged as spam
+        CreationDeposits get(creation_deposit_of): map ReferenceIndex => Option<(T::AccountId, BalanceOf<T>)>;
+        // creator and ReapReward share of the creation deposit, reserved until the vote is reaped
+        ReapRewards get(reap_reward_of): map ReferenceIndex => Option<(T::AccountId, BalanceOf<T>)>;
+        // ballots of votes cleared by reap_vote
+        VoteArchives get(archive_of): map ReferenceIndex => Option<VoteArchive<T::Hash>>;
+        // voters and committers released so far, while a cancelled vote is released over several calls
+        CancelProgress get(cancel_progress_of): map ReferenceIndex => Option<u64>;
+        // voters reaped so far, and the Merkle frontier of their ballots, while a vote is reaped over several calls
+        ReapProgress get(reap_progress_of): map ReferenceIndex => Option<(u64, Vec<Option<T::Hash>>)>;
+        // votes announced ahead of time, opened in on_initialize of their starts_at
+        VoteOpening get(opening_at): map T::BlockNumber => Vec<ReferenceIndex>;
         Data get(data): map u64 => Vec<u8>;
 
-        // VotedAccounts:[aye:[AccountId], nay:[AccountId],....]
-        VotedAccounts: map (ReferenceIndex, u8) => Vec<T::AccountId>;
-        AccountsByOption: map (ReferenceIndex, u8) => Vec<T::AccountId>;
-        VotedOption: map(ReferenceIndex, T::AccountId) => u8;
+        // latest ballot of each voter of a vote
+        Ballots get(ballot_of): double_map ReferenceIndex, blake2_256(T::AccountId) => Option<BallotRecord>;
+        // voters of a vote by the order of their first ballot, Encrypted ones included
+        Voters: map (ReferenceIndex, u64) => T::AccountId;
+        VoterCount get(voter_count): map ReferenceIndex => u64;
+        // running total of each option, updated on every ballot. Aye: 0, Nay: 1, Abstain: 2 for Simple, Lock and CommitReveal
+        OptionTotals get(option_total): map (ReferenceIndex, u8) => VoteWeight;
+        // running number of voters counted for each option. Ranked ballots count for their first preference
+        OptionHeadCounts get(option_head_count): map (ReferenceIndex, u8) => u64;
         VoteOptions: map u64 => Vec<Vec<u8>>;
 
+        // credits spent on a quadratic ballot
+        SpentCredits: map (ReferenceIndex, T::AccountId) => BalanceOf<T>;
+
+        // every elimination round of a concluded RankedChoice vote
+        RunoffResults get(runoff_results): map ReferenceIndex => RunoffResult;
+        // delegators counted through the ballot of a voter of a RankedChoice vote, for instant-runoff
+        Represented get(represented_by): map (ReferenceIndex, T::AccountId) => u64;
+        // counting of a delegable vote concluded over several blocks
+        CountProgress get(count_progress_of): map ReferenceIndex => Option<Counting>;
+        // votes with a CountProgress. delegations they count cannot change until they conclude
+        CountingVotes get(counting_votes): u32;
+
+        // unrevealed commitments of CommitReveal votes, with their reserved deposit
+        Commitments get(commitment_of): map (ReferenceIndex, T::AccountId) => Option<(T::Hash, BalanceOf<T>)>;
+        // committers of a vote by the order of their first commitment
+        Committers: map (ReferenceIndex, u64) => T::AccountId;
+        CommitterCount get(committer_count): map ReferenceIndex => u64;
+
+        // trustees decrypting an Encrypted vote. trustee i holds the key share of index i + 1
+        Trustees get(trustees_of): map ReferenceIndex => Vec<T::AccountId>;
+        // Feldman commitments to the key sharing. the first is the joint public key,
+        // the number of commitments is the number of trustees needed to decrypt
+        TrusteeCommitments get(trustee_commitments): map ReferenceIndex => Vec<Point>;
+        EncryptedBallots: map (ReferenceIndex, T::AccountId) => Option<Ciphertext>;
+        // homomorphic sum of EncryptedBallots
+        EncryptedTotals get(encrypted_total): map ReferenceIndex => Ciphertext;
+        // verified decryption shares of EncryptedTotals as (trustee index, share)
+        DecryptionShares get(decryption_shares): map ReferenceIndex => Vec<(u64, Point)>;
+
+        // delegator and vote (None for every vote) => delegate
+        Delegations get(delegate_of): map (T::AccountId, Option<ReferenceIndex>) => Option<T::AccountId>;
+        // vote (None for every vote) and index => delegator, for counting the delegations of a vote
+        Delegators: map (Option<ReferenceIndex>, u64) => T::AccountId;
+        DelegatorCount get(delegator_count): map Option<ReferenceIndex> => u64;
+        // delegator and vote => index of the delegator in Delegators
+        DelegatorIndex: map (T::AccountId, Option<ReferenceIndex>) => u64;
+
         LockBalance: map (ReferenceIndex, T::AccountId) => LockInfo<BalanceOf<T>, T::BlockNumber>;
+        // votes the account has an entry of LockBalance for
+        LockedVotes get(locked_votes_of): map T::AccountId => Vec<ReferenceIndex>;
         LockCount get(lock_count): u64;
     }
 }
 
 decl_module! {
     pub struct Module<T: Trait> for enum Call where origin: T::Origin {
+        const MaxConclusionWeight: Weight = T::MaxConclusionWeight::get();
+        const RevealPeriod: T::BlockNumber = T::RevealPeriod::get();
+        const RevealDeposit: BalanceOf<T> = T::RevealDeposit::get();
+        const CreationDepositBase: BalanceOf<T> = T::CreationDepositBase::get();
+        const CreationDepositPerByte: BalanceOf<T> = T::CreationDepositPerByte::get();
+        const LockPeriod: T::BlockNumber = T::LockPeriod::get();
+        const RetentionPeriod: T::BlockNumber = T::RetentionPeriod::get();
+        const ReapReward: BalanceOf<T> = T::ReapReward::get();
+        const ProposalQuorum: u64 = T::ProposalQuorum::get();
+        const MaxProposalWeight: Weight = T::MaxProposalWeight::get();
+        const MaxDelegationDepth: u32 = T::MaxDelegationDepth::get();
+
         fn deposit_event() = default;
 
+        // concludes votes expired in the previous block
+        fn on_initialize(n: T::BlockNumber) {
+            if <StorageVersion>::get() < migration::STORAGE_VERSION {
+                migration::on_runtime_upgrade::<T>();
+            }
+            for reference_index in <VoteOpening<T>>::take(n) {
+                // cancelled before opening
+                if !Self::votes(reference_index).concluded {
+                    Self::deposit_event(RawEvent::Opened(reference_index));
+                }
+            }
+        }
+
+        fn on_finalize(n: T::BlockNumber) {
+            let max = T::MaxConclusionWeight::get();
+            let mut budget = max;
+            let mut rest: Vec<ReferenceIndex> = Vec::new();
+            for reference_index in <VoteExpiry<T>>::take(n) {
+                // keeps the order of the votes rolling to the next block
+                if !rest.is_empty() {
+                    rest.push(reference_index);
+                    continue;
+                }
+                if Self::votes(reference_index).concluded {
+                    continue;
+                }
+                let (weight, least) = Self::conclusion_weight(reference_index);
+                let step = weight.saturating_add(accounts_weight(least));
+                if step > max {
+                    Self::deposit_event(RawEvent::ConclusionDeferred(reference_index));
+                } else if step > budget {
+                    rest.push(reference_index);
+                } else {
+                    let max_accounts = u64::from((budget - weight) / CONCLUDE_ACCOUNT_WEIGHT);
+                    match Self::conclude(reference_index, max_accounts) {
+                        Ok(visited) => {
+                            budget = budget.saturating_sub(weight.saturating_add(accounts_weight(visited)));
+                            // counted in part, the rest of it rolls
+                            if !Self::votes(reference_index).concluded {
+                                rest.push(reference_index);
+                            }
+                        }
+                        // an Encrypted vote waiting for decryption shares
+                        Err(_) => budget -= weight,
+                    }
+                }
+            }
+            if !rest.is_empty() {
+                let next = n + One::one();
+                rest.extend(<VoteExpiry<T>>::take(next));
+                <VoteExpiry<T>>::insert(next, rest);
+            }
+        }
+
         // Creator Modules
         // Create a new vote
+        // settings: quorum, threshold, proposal, opening and early unlock of the vote, see VoteSettings
+        // reserves a creation deposit for the bytes stored, returned on conclusion unless quorum is not met
+        // exp_length counts from the opening
         // TODO: Takes expiring time, title as data: Vec, voting_type
-        pub fn create_vote(origin, vote_type:u8, exp_length: T::BlockNumber, data: Vec<u8>, cert_index: u64, options: Vec<Vec<u8>>) -> Result {
+        pub fn create_vote(origin, vote_type: VoteType, exp_length: T::BlockNumber, data: Vec<u8>, cert_index: u64, options: Vec<Vec<u8>>, settings: VoteSettingsOf<T>) -> Result {
             let sender = ensure_signed(origin)?;
+            let VoteSettings { quorum, threshold, proposal, starts_at, early_unlock } = settings;
             ensure!(data.len() <= 256, "listing data cannot be more than 256 bytes");
-            ensure!(vote_type < 3, "Unknown voteType was provided.");
-            ensure!(vote_type >= 0, "Unknown voteType was provided.");
             let new_vote_num = <AllVoteCount>::get().checked_add(1)
                 .ok_or("Overflow adding vote count")?;
             let vote_count_by_sender = <CreatedVoteCount<T>>::get(sender.clone()).checked_add(1)
                 .ok_or("Overflow adding vote count to the sender")?;
             // let exp_length = 60000.into(); // 30sec for test
             let now = <system::Module<T>>::block_number();
+            let starts_at = starts_at.unwrap_or(now);
+            ensure!(starts_at >= now, "Vote cannot start in the past.");
             // check if resolved if now > vote_exp
-            let vote_exp = now.checked_add(&exp_length.into()).ok_or("Overflow when setting application expiry.")?;
+            let vote_exp = starts_at.checked_add(&exp_length.into()).ok_or("Overflow when setting application expiry.")?;
+            // concluded in on_finalize of the first block after ballots close
+            let closes = if vote_type == VoteType::CommitReveal {
+                vote_exp.checked_add(&T::RevealPeriod::get()).ok_or("Overflow when setting application expiry.")?
+            } else {
+                vote_exp
+            };
+            let conclusion_block = closes.checked_add(&One::one()).ok_or("Overflow when setting application expiry.")?;
 
             let ca_hash:T::Hash;
             if cert_index != 0 {
@@ -129,18 +567,57 @@ decl_module! {
                 approved: ca_hash,
                 creator: sender.clone(),
                 when: now,
+                starts_at,
                 vote_ends: vote_exp,
                 concluded: false,
+                quorum,
+                threshold,
             };
 
             // // options
             ensure!(!<VoteOptions>::exists(new_vote_num), "Vote already exists in option storage");
-            ensure!((options.len() as u8) > 0, "At least one option should be provided.");
-            ensure!((options.len() as u8) < 255, "Cannot add more than 254 options");
+            Self::validate_vote_type(vote_type, &options, cert_index)?;
+            ensure!(threshold.is_valid(), "Super majority should be more than half.");
+            if let Some(proposal) = &proposal {
+                ensure!(cert_index != 0, "Votes with a proposal require a certificate.");
+                ensure!(quorum >= T::ProposalQuorum::get(), "Quorum is below the minimum of votes with a proposal.");
+                ensure!(proposal.get_dispatch_info().weight <= T::MaxProposalWeight::get(), "Proposal weighs more than MaxProposalWeight.");
+            }
+            if early_unlock.is_some() {
+                ensure!(vote_type == VoteType::Lock, "Early withdrawal only applies to lockvotes.");
+            }
+
+            let mut stored_bytes = new_vote.encode().len() + data.len() + options.encode().len() + proposal.encode().len();
+            // RunoffResults holds up to a round per option, each with a total per option
+            if vote_type == VoteType::RankedChoice {
+                let round = RunoffRound { totals: options.iter().map(|_| 0).collect(), eliminated: Some(0) };
+                stored_bytes += round.encode().len() * options.len();
+            }
+            let deposit = T::CreationDepositPerByte::get()
+                .saturating_mul((stored_bytes as u64).saturated_into())
+                .saturating_add(T::CreationDepositBase::get());
+            T::Currency::reserve(&sender, deposit).map_err(|_| "Not enough free balance for the creation deposit.")?;
+            let reward = T::ReapReward::get().min(deposit);
+            <CreationDeposits<T>>::insert(new_vote_num, (sender.clone(), deposit - reward));
+            if !reward.is_zero() {
+                <ReapRewards<T>>::insert(new_vote_num, (sender.clone(), reward));
+            }
             <VoteOptions>::insert(new_vote_num, options);
 
             Self::mint_vote(sender, new_vote, vote_count_by_sender, new_vote_num)?;
             <Data>::insert(new_vote_num, data);
+            if let Some(proposal) = proposal {
+                <Proposals<T>>::insert(new_vote_num, *proposal);
+            }
+            if let Some(early_unlock) = early_unlock {
+                <EarlyUnlocks>::insert(new_vote_num, early_unlock);
+            }
+            <VoteExpiry<T>>::mutate(conclusion_block, |due| due.push(new_vote_num));
+            if starts_at == now {
+                Self::deposit_event(RawEvent::Opened(new_vote_num));
+            } else {
+                <VoteOpening<T>>::mutate(starts_at, |opening| opening.push(new_vote_num));
+            }
             Ok(())
         }
 
@@ -148,65 +625,542 @@ decl_module! {
             let sender = ensure_signed(origin)?;
             let vote = Self::votes(&reference_index);
             let now = <system::Module<T>>::block_number();
-            let lock_id: LockIdentifier = reference_index.to_be_bytes();
-            let current_blocknumber = <system::Module<T>>::block_number();
+            let until = now.checked_add(&duration).ok_or("Overflow when setting the lock duration.")?;
             // duration should be at least vote_end
             // deposit should be smaller than freebalance
-            ensure!(now + duration >= vote.vote_ends, "Lock duration should be or bigger than vote expiry.");
-            ensure!(!<LockBalance<T>>::exists((&reference_index, &sender)), "You cannot lockvote twice.");
+            ensure!(until >= vote.vote_ends, "Lock duration should be or bigger than vote expiry.");
+            ensure!(!<LockBalance<T>>::exists((&reference_index, &sender)), "You cannot lockvote twice. Use extend_lockvote instead.");
             ensure!(T::Currency::free_balance(&sender) > deposit, "You cannot lock more than your free balance!");
             ensure!(<VotesByIndex<T>>::exists(&reference_index), "Vote doesn't exists");
-            ensure!(vote.creator != sender, "You cannot vote your own vote.");
+            Self::ensure_can_vote(&vote, &sender)?;
+            ensure!(vote.vote_type == VoteType::Lock, "This vote is not LockVote.");
+            ensure!(until <= Self::max_lock_until(&vote), "Lock duration exceeds the longest conviction period.");
+            let multiplier = Self::conviction(&vote, until)
+                .ok_or("Lock duration is shorter than the shortest conviction period.")?;
+            // lock function
+            Self::lock_deposit(reference_index, &sender, LockInfo {
+                deposit,
+                duration,
+                until,
+            });
+            Self::cast_ballot_f(sender.clone(), reference_index, ballot)?; // includes checks
+            let weight = Self::lock_weight(deposit, multiplier);
+            Self::deposit_event(RawEvent::LockVoted(sender, reference_index, ballot, deposit, multiplier, weight));
+            Ok(())
+        }
+
+        // Tops up the deposit and/or extends the lock of a lockvote while the vote is open.
+        // The conviction multiplier is recomputed for the new until.
+        fn extend_lockvote(origin, reference_index: ReferenceIndex, extra_deposit: BalanceOf<T>, extra_duration: T::BlockNumber) -> Result {
+            let sender = ensure_signed(origin)?;
+            ensure!(<VotesByIndex<T>>::exists(&reference_index), "Vote doesn't exists");
+            let vote = Self::votes(&reference_index);
+            let now = <system::Module<T>>::block_number();
             ensure!(vote.vote_ends > now, "This vote has already been expired.");
-            ensure!(vote.vote_type == 1, "This vote is not LockVote.");
-            
-            if vote.approved != T::Hash::default() {
-              // fails is the sender's account is not registered for CAHash.
-                certificate::Module::<T>::check_account(sender.clone(), vote.approved)?;
+            ensure!(!vote.concluded, "This vote has been cancelled.");
+            ensure!(vote.vote_type == VoteType::Lock, "This vote is not LockVote.");
+            ensure!(<LockBalance<T>>::exists((&reference_index, &sender)), "You have no lockvote to extend.");
+            ensure!(!extra_deposit.is_zero() || !extra_duration.is_zero(), "Nothing to extend.");
+
+            let mut info = <LockBalance<T>>::get((&reference_index, &sender));
+            info.deposit = info.deposit.checked_add(&extra_deposit).ok_or("Overflow adding to the deposit.")?;
+            info.duration = info.duration.checked_add(&extra_duration).ok_or("Overflow extending the lock.")?;
+            info.until = info.until.checked_add(&extra_duration).ok_or("Overflow extending the lock.")?;
+            ensure!(T::Currency::free_balance(&sender) > info.deposit, "You cannot lock more than your free balance!");
+            ensure!(info.until <= Self::max_lock_until(&vote), "Lock duration exceeds the longest conviction period.");
+            let multiplier = Self::conviction(&vote, info.until).unwrap_or(0);
+
+            let (deposit, until) = (info.deposit, info.until);
+            <LockBalance<T>>::insert((&reference_index, &sender), info);
+            Self::update_lock(&sender);
+            let weight = Self::lock_weight(deposit, multiplier);
+            if let Some(record) = <Ballots<T>>::get(reference_index, &sender) {
+                Self::record_ballot(&vote, &sender, BallotRecord { weight: weight.saturated_into(), ..record });
             }
-            // lock function
-            <LockBalance<T>>::mutate((&reference_index, &sender), |lockinfo| {
-                lockinfo.deposit += deposit;
-                lockinfo.duration = duration;
-                lockinfo.until = current_blocknumber + duration;
+            Self::deposit_event(RawEvent::LockExtended(sender, reference_index, deposit, until, multiplier, weight));
+            Ok(())
+        }
+
+        // Spends credits on one of VoteOptions. Vote weight is the square root of the credits.
+        // Credits are locked until the vote ends or burned, depending on the CreditPolicy of the vote.
+        // checks:
+            // a: the vote is Quadratic and hasn't expired
+            // b: the sender is registered for the CAHash of the vote, so 1 person has 1 ballot
+            // c: the sender hasn't spent credits on this vote yet
+        fn cast_quadratic_vote(origin, reference_index: ReferenceIndex, option: u8, credits: BalanceOf<T>) -> Result {
+            let sender = ensure_signed(origin)?;
+            ensure!(<VotesByIndex<T>>::exists(&reference_index), "Vote doesn't exists");
+            let vote = Self::votes(&reference_index);
+            let now = <system::Module<T>>::block_number();
+            Self::ensure_can_vote(&vote, &sender)?;
+            let policy = match vote.vote_type {
+                VoteType::Quadratic(policy) => policy,
+                _ => return Err("This vote is not a quadratic vote."),
+            };
+            ensure!(!<SpentCredits<T>>::exists((&reference_index, &sender)), "You cannot spend credits twice.");
+            ensure!(!credits.is_zero(), "You need to spend credits to vote.");
+            ensure!((option as usize) < <VoteOptions>::get(reference_index).len(), "Provided option out of range.");
+            ensure!(T::Currency::free_balance(&sender) > credits, "You cannot spend more than your free balance!");
+
+            match policy {
+                CreditPolicy::Lock => {
+                    Self::lock_deposit(reference_index, &sender, LockInfo {
+                        deposit: credits,
+                        duration: vote.vote_ends - now,
+                        until: vote.vote_ends,
+                    });
+                }
+                CreditPolicy::Burn => {
+                    // dropping the imbalance reduces total issuance
+                    let _ = T::Currency::withdraw(
+                        &sender,
+                        credits,
+                        WithdrawReason::Transfer.into(),
+                        ExistenceRequirement::KeepAlive,
+                    )?;
+                }
+            }
+            <SpentCredits<T>>::insert((&reference_index, &sender), credits);
+            Self::record_ballot(&vote, &sender, BallotRecord {
+                ballot: OptionBallot::Single(option),
+                weight: credits.saturated_into::<VoteWeight>().integer_sqrt(),
             });
-            let until = u64::max_value();
-            T::Currency::set_lock(
-                lock_id,
-                &sender,
-                deposit,
-                until.saturated_into::<T::BlockNumber>(),   // use withdraw function
-                WithdrawReasons::except(WithdrawReason::TransactionPayment),
+
+            Self::deposit_event(RawEvent::QuadraticVoted(sender, reference_index, option, credits));
+            print("Quadratic ballot casted!");
+            Ok(())
+        }
+
+        // Ranks VoteOptions, most preferred first. Options left out are not ranked.
+        // Casting again replaces the ranking.
+        fn cast_ranked_ballot(origin, reference_index: ReferenceIndex, ranking: Vec<u8>) -> Result {
+            let sender = ensure_signed(origin)?;
+            ensure!(<VotesByIndex<T>>::exists(&reference_index), "Vote doesn't exists");
+            let vote = <VotesByIndex<T>>::get(&reference_index);
+            Self::ensure_can_vote(&vote, &sender)?;
+            ensure!(vote.vote_type == VoteType::RankedChoice, "This vote is not a ranked choice vote.");
+
+            let option_count = <VoteOptions>::get(reference_index).len();
+            ensure!(!ranking.is_empty(), "Ranking should contain at least one option.");
+            ensure!(ranking.len() <= option_count, "Ranking cannot contain more options than the vote.");
+            for (i, option) in ranking.iter().enumerate() {
+                ensure!((*option as usize) < option_count, "Provided option out of range.");
+                ensure!(!ranking[..i].contains(option), "An option can be ranked only once.");
+            }
+
+            let ballot = OptionBallot::Ranked(ranking);
+            Self::record_ballot(&vote, &sender, BallotRecord { ballot: ballot.clone(), weight: 1 });
+            Self::deposit_event(RawEvent::OptionVoted(sender, reference_index, ballot));
+            print("Ranked ballot casted!");
+            Ok(())
+        }
+
+        // Approves any subset of VoteOptions. Casting again replaces the approvals.
+        fn cast_approval_ballot(origin, reference_index: ReferenceIndex, approved: Vec<u8>) -> Result {
+            let sender = ensure_signed(origin)?;
+            ensure!(<VotesByIndex<T>>::exists(&reference_index), "Vote doesn't exists");
+            let vote = <VotesByIndex<T>>::get(&reference_index);
+            Self::ensure_can_vote(&vote, &sender)?;
+            ensure!(vote.vote_type == VoteType::Approval, "This vote is not an approval vote.");
+
+            let option_count = <VoteOptions>::get(reference_index).len();
+            ensure!(!approved.is_empty(), "Approve at least one option.");
+            for (i, option) in approved.iter().enumerate() {
+                ensure!((*option as usize) < option_count, "Provided option out of range.");
+                ensure!(!approved[..i].contains(option), "An option can be approved only once.");
+            }
+
+            let ballot = OptionBallot::Approval(approved);
+            Self::record_ballot(&vote, &sender, BallotRecord { ballot: ballot.clone(), weight: 1 });
+            Self::deposit_event(RawEvent::OptionVoted(sender, reference_index, ballot));
+            print("Approval ballot casted!");
+            Ok(())
+        }
+
+        // Scores every one of VoteOptions, in their order. Casting again replaces the scores.
+        fn cast_score_ballot(origin, reference_index: ReferenceIndex, scores: Vec<u8>) -> Result {
+            let sender = ensure_signed(origin)?;
+            ensure!(<VotesByIndex<T>>::exists(&reference_index), "Vote doesn't exists");
+            let vote = <VotesByIndex<T>>::get(&reference_index);
+            Self::ensure_can_vote(&vote, &sender)?;
+            let max_score = match vote.vote_type {
+                VoteType::Score(max_score) => max_score,
+                _ => return Err("This vote is not a score vote."),
+            };
+
+            ensure!(scores.len() == <VoteOptions>::get(reference_index).len(), "Provide one score for each option.");
+            ensure!(scores.iter().all(|score| *score <= max_score), "Score is above the maximum of this vote.");
+
+            let ballot = OptionBallot::Score(scores);
+            Self::record_ballot(&vote, &sender, BallotRecord { ballot: ballot.clone(), weight: 1 });
+            Self::deposit_event(RawEvent::OptionVoted(sender, reference_index, ballot));
+            print("Score ballot casted!");
+            Ok(())
+        }
+
+        // Commits to a CommitReveal ballot without publishing it.
+        // commitment: hash of the SCALE encoded (reference_index, sender, Ballot, salt), so it opens only for
+        // its voter and vote. Committing again replaces the commitment.
+        // RevealDeposit is reserved on the first commitment, returned on reveal and slashed if never revealed.
+        fn commit_ballot(origin, reference_index: ReferenceIndex, commitment: T::Hash) -> Result {
+            let sender = ensure_signed(origin)?;
+            ensure!(<VotesByIndex<T>>::exists(&reference_index), "Vote doesn't exists");
+            let vote = <VotesByIndex<T>>::get(&reference_index);
+            Self::ensure_can_vote(&vote, &sender)?;
+            ensure!(vote.vote_type == VoteType::CommitReveal, "This vote is not a commit-reveal vote.");
+
+            let deposit = match <Commitments<T>>::get((&reference_index, &sender)) {
+                Some((_, deposit)) => deposit,
+                None => {
+                    let deposit = T::RevealDeposit::get();
+                    T::Currency::reserve(&sender, deposit)?;
+                    let count = <CommitterCount>::get(reference_index);
+                    <Committers<T>>::insert((reference_index, count), &sender);
+                    <CommitterCount>::insert(reference_index, count + 1);
+                    deposit
+                }
+            };
+            <Commitments<T>>::insert((&reference_index, &sender), (commitment, deposit));
+            Self::deposit_event(RawEvent::Committed(sender, reference_index));
+            Ok(())
+        }
+
+        // Opens a commitment between vote_ends and the end of RevealPeriod.
+        // Only revealed ballots are tallied.
+        fn reveal_ballot(origin, reference_index: ReferenceIndex, ballot: Ballot, salt: Vec<u8>) -> Result {
+            let sender = ensure_signed(origin)?;
+            let vote = <VotesByIndex<T>>::get(&reference_index);
+            let now = <system::Module<T>>::block_number();
+            ensure!(<VotesByIndex<T>>::exists(&reference_index), "Vote doesn't exists");
+            ensure!(vote.vote_type == VoteType::CommitReveal, "This vote is not a commit-reveal vote.");
+            ensure!(now >= vote.vote_ends, "Reveal period has not started yet.");
+            ensure!(now <= Self::ballots_close(&vote), "Reveal period is over.");
+
+            let (commitment, deposit) = <Commitments<T>>::get((&reference_index, &sender))
+                .ok_or("You have no commitment to reveal.")?;
+            ensure!(T::Hashing::hash_of(&(reference_index, &sender, ballot, &salt)) == commitment, "Revealed ballot doesn't match the commitment.");
+
+            <Commitments<T>>::remove((&reference_index, &sender));
+            T::Currency::unreserve(&sender, deposit);
+            Self::cast_ballot_f(sender, reference_index, ballot)?;
+            Ok(())
+        }
+
+        // Registers the trustees of an Encrypted vote before any ballot is cast.
+        // commitments: Feldman commitments to the trustees' key shares, threshold = commitments.len()
+        fn set_trustees(origin, reference_index: ReferenceIndex, trustees: Vec<T::AccountId>, commitments: Vec<Point>) -> Result {
+            let sender = ensure_signed(origin)?;
+            let vote = <VotesByIndex<T>>::get(&reference_index);
+            ensure!(<VotesByIndex<T>>::exists(&reference_index), "Vote doesn't exists");
+            ensure!(vote.creator == sender, "Only the creator can set trustees.");
+            ensure!(vote.vote_type == VoteType::Encrypted, "This vote is not an encrypted vote.");
+            ensure!(<VoterCount>::get(&reference_index) == 0, "Trustees cannot change after ballots are cast.");
+            ensure!(!commitments.is_empty(), "At least one trustee is needed to decrypt.");
+            ensure!(commitments.len() <= trustees.len(), "Threshold cannot exceed the number of trustees.");
+            for (i, trustee) in trustees.iter().enumerate() {
+                ensure!(!trustees[..i].contains(trustee), "Trustees should be distinct.");
+            }
+            for index in 1..=trustees.len() as u64 {
+                elgamal::verification_key(&commitments, index)?;
+            }
+
+            <Trustees<T>>::insert(reference_index, &trustees);
+            <TrusteeCommitments>::insert(reference_index, commitments);
+            Self::deposit_event(RawEvent::TrusteesSet(reference_index, trustees));
+            Ok(())
+        }
+
+        // Casts an Aye/Nay ballot encrypted to the trustees' joint public key.
+        // proof shows the ciphertext encrypts 0 or 1 and is bound to (reference_index, sender).
+        // Casting again replaces the previous ballot.
+        fn cast_encrypted_ballot(origin, reference_index: ReferenceIndex, ciphertext: Ciphertext, proof: BallotProof) -> Result {
+            let sender = ensure_signed(origin)?;
+            ensure!(<VotesByIndex<T>>::exists(&reference_index), "Vote doesn't exists");
+            let vote = <VotesByIndex<T>>::get(&reference_index);
+            Self::ensure_can_vote(&vote, &sender)?;
+            ensure!(vote.vote_type == VoteType::Encrypted, "This vote is not an encrypted vote.");
+            let commitments = <TrusteeCommitments>::get(&reference_index);
+            ensure!(!commitments.is_empty(), "Trustees are not set yet.");
+            elgamal::verify_ballot(&commitments[0], &ciphertext, &proof, &(reference_index, &sender).encode())?;
+
+            let mut total = <EncryptedTotals>::get(&reference_index);
+            match <EncryptedBallots<T>>::get((&reference_index, &sender)) {
+                Some(previous) => total = total.sub(&previous)?,
+                None => Self::add_voter(reference_index, &sender),
+            }
+            <EncryptedTotals>::insert(reference_index, total.add(&ciphertext)?);
+            <EncryptedBallots<T>>::insert((&reference_index, &sender), ciphertext);
+            Self::deposit_event(RawEvent::EncryptedVoted(sender, reference_index));
+            Ok(())
+        }
+
+        // Trustees submit their share of the decryption of EncryptedTotals after the vote expired,
+        // with a proof it was computed with their key share.
+        // The vote concludes once threshold shares are in.
+        // max_voters: at least the number of voters of the vote. the call weighs decrypting the sum of as many ballots
+        #[weight = ConcludeWeight::<T>(PhantomData)]
+        fn submit_decryption_share(origin, reference_index: ReferenceIndex, share: Point, proof: DecryptionProof, max_voters: u64) -> Result {
+            let sender = ensure_signed(origin)?;
+            let vote = <VotesByIndex<T>>::get(&reference_index);
+            let now = <system::Module<T>>::block_number();
+            ensure!(<VotesByIndex<T>>::exists(&reference_index), "Vote doesn't exists");
+            ensure!(vote.vote_type == VoteType::Encrypted, "This vote is not an encrypted vote.");
+            ensure!(now > vote.vote_ends, "This vote hasn't been expired yet.");
+            ensure!(!vote.concluded, "This vote has already concluded.");
+            ensure!(<VoterCount>::get(&reference_index) <= max_voters, "The vote has more voters than max_voters.");
+            let index = <Trustees<T>>::get(&reference_index).iter().position(|trustee| trustee == &sender)
+                .ok_or("You are not a trustee of this vote.")? as u64 + 1;
+            let mut shares = <DecryptionShares>::get(&reference_index);
+            ensure!(!shares.iter().any(|(submitted, _)| *submitted == index), "You have already submitted a share.");
+
+            let commitments = <TrusteeCommitments>::get(&reference_index);
+            let key = elgamal::verification_key(&commitments, index)?;
+            elgamal::verify_decryption_share(&key, &<EncryptedTotals>::get(&reference_index), &share, &proof)?;
+            shares.push((index, share));
+            <DecryptionShares>::insert(reference_index, &shares);
+            Self::deposit_event(RawEvent::DecryptionShareSubmitted(sender, reference_index));
+
+            if shares.len() == commitments.len() {
+                Self::conclude(reference_index, max_voters)?;
+            }
+            Ok(())
+        }
+
+        // Slashes the creation deposit of a vote flagged as spam. Only SpamOrigin can call.
+        // The ReapReward share stays reserved for whoever reaps the vote.
+        fn flag_spam(origin, reference_index: ReferenceIndex) -> Result {
+            T::SpamOrigin::ensure_origin(origin)?;
+            ensure!(<VotesByIndex<T>>::exists(&reference_index), "Vote doesn't exists");
+            ensure!(<CreationDeposits<T>>::exists(&reference_index), "This vote has no deposit to slash.");
+            Self::settle_deposit(reference_index, true);
+            Ok(())
+        }
+
+        // Cancels a vote before it concludes. The creator can cancel until vote_ends while no ballot is cast,
+        // root and CancelOrigin at any time. The creation deposit is returned, unless the creator cancels after the vote opened.
+        // Drops the proposal, unlocks every lockvote and credit lock and returns reveal deposits.
+        // Releases up to max_accounts voters and committers a call. Anyone can call again to release the rest.
+        // Credits burned on a Quadratic(Burn) vote left total issuance and are not refunded: CreditsForfeited records them.
+        // The vote is marked concluded with Outcome::Cancelled by the first call.
+        #[weight = CancelWeight]
+        fn cancel_vote(origin, reference_index: ReferenceIndex, max_accounts: u64) -> Result {
+            ensure!(<VotesByIndex<T>>::exists(&reference_index), "Vote doesn't exists");
+            let vote = Self::votes(reference_index);
+            let released = match <CancelProgress>::get(reference_index) {
+                Some(released) => released,
+                None => {
+                    ensure!(!vote.concluded, "This vote has already concluded.");
+                    let privileged = ensure_root(origin.clone()).is_ok() || T::CancelOrigin::ensure_origin(origin.clone()).is_ok();
+                    let mut slash = false;
+                    if !privileged {
+                        let sender = ensure_signed(origin)?;
+                        let now = <system::Module<T>>::block_number();
+                        ensure!(vote.creator == sender, "Only the creator can cancel this vote.");
+                        ensure!(vote.vote_ends > now, "This vote has already been expired.");
+                        ensure!(!Self::has_ballots(&vote), "Ballots have already been cast.");
+                        // an opened vote was announced to voters for nothing
+                        slash = now >= vote.starts_at;
+                    }
+                    Self::settle_deposit(reference_index, slash);
+                    <Proposals<T>>::remove(reference_index);
+                    Self::stop_counting(reference_index);
+                    <VoteOutcomes>::insert(reference_index, Outcome::Cancelled);
+                    <VotesByIndex<T>>::mutate(&reference_index, |vote| vote.concluded = true);
+                    Self::deposit_event(RawEvent::Cancelled(reference_index));
+                    0
+                }
+            };
+
+            // voters first, then committers
+            let voters = <VoterCount>::get(reference_index);
+            let accounts = voters.saturating_add(<CommitterCount>::get(reference_index));
+            let last = accounts.min(released.saturating_add(max_accounts));
+            for index in released..last {
+                if index < voters {
+                    let voter = <Voters<T>>::get((reference_index, index));
+                    if <LockBalance<T>>::exists((&reference_index, &voter)) {
+                        Self::release_lock(reference_index, &voter);
+                        Self::update_lock(&voter);
+                    }
+                    if vote.vote_type == VoteType::Quadratic(CreditPolicy::Burn) {
+                        let credits = <SpentCredits<T>>::get((&reference_index, &voter));
+                        Self::deposit_event(RawEvent::CreditsForfeited(voter, reference_index, credits));
+                    }
+                } else {
+                    let committer = <Committers<T>>::take((reference_index, index - voters));
+                    if let Some((_, deposit)) = <Commitments<T>>::take((reference_index, &committer)) {
+                        T::Currency::unreserve(&committer, deposit);
+                    }
+                }
+            }
+            if last < accounts {
+                <CancelProgress>::insert(reference_index, last);
+                Self::deposit_event(RawEvent::CancelPending(reference_index, accounts - last));
+            } else {
+                <CancelProgress>::remove(reference_index);
+                <CommitterCount>::remove(reference_index);
+            }
+            Ok(())
+        }
+
+        // Clears the per-voter data of a vote concluded RetentionPeriod ago. Anyone can call.
+        // Vote, VoteResults and VoteOutcomes stay, with a VoteArchive of the ballots.
+        // Reaps up to max_accounts voters a call, in the order of Voters, until every voter is reaped.
+        // Then delegations for the vote are cleared, whether their delegate voted or not, from the same max_accounts.
+        // Expired locks are released, a voter with an active lock cannot be reaped yet.
+        // The caller reaping the last voters receives the ReapReward share of the creation deposit,
+        // kept by votes flagged as spam too, so that they are cleared as well.
+        #[weight = ReapWeight]
+        fn reap_vote(origin, reference_index: ReferenceIndex, max_accounts: u64) -> Result {
+            let sender = ensure_signed(origin)?;
+            ensure!(<VotesByIndex<T>>::exists(&reference_index), "Vote doesn't exists");
+            let vote = Self::votes(reference_index);
+            let now = <system::Module<T>>::block_number();
+            ensure!(vote.concluded, "This vote has not concluded yet.");
+            ensure!(!<VoteArchives<T>>::exists(&reference_index), "This vote has already been reaped.");
+            ensure!(!<CancelProgress>::exists(&reference_index), "This vote is still being cancelled.");
+            ensure!(now > Self::ballots_close(&vote).saturating_add(T::RetentionPeriod::get()), "The retention period of this vote is not over yet.");
+            let count = <VoterCount>::get(reference_index);
+            let (reaped, mut frontier) = <ReapProgress<T>>::get(reference_index).unwrap_or_default();
+            let last = count.min(reaped.saturating_add(max_accounts));
+            let voters: Vec<T::AccountId> = (reaped..last)
+                .map(|index| <Voters<T>>::get((reference_index, index)))
+                .collect();
+            ensure!(
+                voters.iter().all(|voter| !<LockBalance<T>>::exists((reference_index, voter)) || <LockBalance<T>>::get((reference_index, voter)).until < now),
+                "Locks of this vote are still active."
             );
-            Self::cast_ballot_f(sender, reference_index, ballot)?; // includes checks
+
+            for (index, voter) in (reaped..last).zip(voters.iter()) {
+                let leaf = match <Ballots<T>>::take(reference_index, voter) {
+                    Some(record) => T::Hashing::hash_of(&(voter, record)),
+                    None => T::Hashing::hash_of(&(voter, <EncryptedBallots<T>>::take((reference_index, voter)).unwrap_or_default())),
+                };
+                Self::merkle_push(&mut frontier, leaf);
+                if <LockBalance<T>>::exists((reference_index, voter)) {
+                    Self::release_lock(reference_index, voter);
+                    Self::update_lock(voter);
+                }
+                <SpentCredits<T>>::remove((reference_index, voter));
+                <Represented<T>>::remove((reference_index, voter));
+                <Voters<T>>::remove((reference_index, index));
+            }
+            if last == count {
+                // popped from the end of Delegators, so no delegator moves
+                let delegators = <DelegatorCount>::get(Some(reference_index));
+                let cleared = delegators.min(max_accounts - (last - reaped));
+                for index in (delegators - cleared..delegators).rev() {
+                    let delegator = <Delegators<T>>::get((Some(reference_index), index));
+                    Self::remove_delegation(&delegator, Some(reference_index));
+                }
+            }
+            let left = (count - last).saturating_add(<DelegatorCount>::get(Some(reference_index)));
+            if left > 0 {
+                <ReapProgress<T>>::insert(reference_index, (last, frontier));
+                Self::deposit_event(RawEvent::ReapPending(reference_index, left));
+                return Ok(());
+            }
+
+            let reward = match <ReapRewards<T>>::take(reference_index) {
+                Some((creator, reward)) => {
+                    T::Currency::repatriate_reserved(&creator, &sender, reward)?;
+                    reward
+                }
+                None => Zero::zero(),
+            };
+            <ReapProgress<T>>::remove(reference_index);
+            <VoterCount>::remove(reference_index);
+            // Simple, Lock and CommitReveal count 3 options whatever VoteOptions holds
+            for option in 0..<VoteOptions>::take(reference_index).len().max(3) as u8 {
+                <OptionTotals>::remove((reference_index, option));
+                <OptionHeadCounts>::remove((reference_index, option));
+            }
+            <Data>::remove(reference_index);
+            <RunoffResults>::remove(reference_index);
+            <EarlyUnlocks>::remove(reference_index);
+            <Trustees<T>>::remove(reference_index);
+            <TrusteeCommitments>::remove(reference_index);
+            <EncryptedTotals>::remove(reference_index);
+            <DecryptionShares>::remove(reference_index);
+
+            <VoteArchives<T>>::insert(reference_index, VoteArchive {
+                ballot_root: Self::merkle_root(&frontier),
+                ballots: count,
+            });
+            Self::deposit_event(RawEvent::Reaped(sender, reference_index, reward));
             Ok(())
         }
 
         // Withdraws locked token
         // Takes reference_index and sender accountId
         // checks:
-            // a: if the vote exists and type is 1
+            // a: if the vote exists and type is Lock, or Quadratic with locked credits
             // b: the vote has concluded. Cannot tally if withdrawn before conclusion 
             // b: ensure sender has locked the vote
             // c: ensure the lock period is over
         fn withdraw(origin, reference_index: ReferenceIndex) -> Result {
             let sender = ensure_signed(origin)?;
             let vote = Self::votes(reference_index);
-            ensure!(vote.vote_type == 1, "This must be lockvote: vote_type: 1!");
+            ensure!(
+                vote.vote_type == VoteType::Lock || vote.vote_type == VoteType::Quadratic(CreditPolicy::Lock),
+                "This must be lockvote: vote_type: Lock!"
+            );
             ensure!(vote.concluded == true, "You have to wait at least until the vote concludes!");
             ensure!(<LockBalance<T>>::exists((&reference_index, &sender)), "You need to participate lockvoting to call this function!");
             let lock_info = <LockBalance<T>>::get((&reference_index, &sender));
             ensure!(lock_info.until < <system::Module<T>>::block_number(), "You need to wait until the lock period is over!");
-            T::Currency::remove_lock(
-                reference_index.to_be_bytes(),
-                &sender
-            );
-            <LockBalance<T>>::remove((&reference_index, &sender));
+            Self::release_lock(reference_index, &sender);
+            // shrinks the governance lock to the deposits still locked
+            Self::update_lock(&sender);
             print("Locked token is withdrawn!");
-            Self::deposit_event(RawEvent::Withdrew(sender, reference_index));
     
             Ok(())
         }
+
+        // Withdraws from a concluded vote before the lock period is over, if the creator allowed it.
+        // The penalty share of the deposit is slashed to the treasury or burned, the rest is released.
+        fn withdraw_early(origin, reference_index: ReferenceIndex) -> Result {
+            let sender = ensure_signed(origin)?;
+            let vote = Self::votes(reference_index);
+            let policy = <EarlyUnlocks>::get(reference_index).ok_or("This vote doesn't allow early withdrawal.")?;
+            ensure!(vote.concluded, "You have to wait at least until the vote concludes!");
+            ensure!(<LockBalance<T>>::exists((&reference_index, &sender)), "You need to participate lockvoting to call this function!");
+            let lock_info = <LockBalance<T>>::get((&reference_index, &sender));
+            ensure!(lock_info.until >= <system::Module<T>>::block_number(), "The lock period is over. Use withdraw instead.");
+
+            let penalty = policy.penalty * lock_info.deposit;
+            // slash takes what the free balance holds of the penalty and returns the rest
+            let (imbalance, remaining) = T::Currency::slash(&sender, penalty);
+            let slashed = penalty - remaining;
+            match policy.destination {
+                PenaltyDestination::Treasury => T::Treasury::on_unbalanced(imbalance),
+                // dropping the imbalance reduces total issuance
+                PenaltyDestination::Burn => drop(imbalance),
+            }
+            Self::release_lock(reference_index, &sender);
+            Self::update_lock(&sender);
+            Self::deposit_event(RawEvent::WithdrewEarly(sender, reference_index, slashed));
+            Ok(())
+        }
+
+        // Withdraws from every concluded vote whose lock period is over, in one call,
+        // and shrinks the governance lock to the largest deposit still locked.
+        fn withdraw_all(origin) -> Result {
+            let sender = ensure_signed(origin)?;
+            let now = <system::Module<T>>::block_number();
+            let released: Vec<ReferenceIndex> = <LockedVotes<T>>::get(&sender).into_iter()
+                .filter(|reference_index| {
+                    Self::votes(reference_index).concluded
+                        && <LockBalance<T>>::get((reference_index, &sender)).until < now
+                })
+                .collect();
+            ensure!(!released.is_empty(), "No lock can be withdrawn yet.");
+            for reference_index in released {
+                Self::release_lock(reference_index, &sender);
+            }
+            Self::update_lock(&sender);
+            Ok(())
+        }
         // Voter modules
         // cast_ballot checks
             // a. the vote exists
@@ -214,147 +1168,339 @@ decl_module! {
             // c. the voter hasnt voted yet in the same option. If voted in different option, change the vote.
         fn cast_ballot(origin, reference_index: ReferenceIndex, ballot: Ballot) -> Result {
             let sender = ensure_signed(origin)?;
-            let vote = <VotesByIndex<T>>::get(&reference_index);
-            let now = <system::Module<T>>::block_number();
             ensure!(<VotesByIndex<T>>::exists(&reference_index), "Vote doesn't exists");
-            ensure!(vote.creator != sender, "You cannot vote your own vote.");
-            ensure!(vote.vote_ends > now, "This vote has already been expired.");
-            ensure!(vote.vote_type == 0, "This vote is LockVote. Use 'cast_lockvote' instead!");
-
-            if vote.approved != T::Hash::default() {
-              // fails is the sender's account is not registered for CAHash.
-                certificate::Module::<T>::check_account(sender.clone(), vote.approved)?;
-            }
-            let mut accounts_aye = <VotedAccounts<T>>::get((reference_index, 0));
-            let mut accounts_nay = <VotedAccounts<T>>::get((reference_index, 1));
-            // keep track of voter's id in aye or nay vector in Vote
-            // Voter can change his vote b/w aye and nay
-            // Voter cannot vote twice
-            match ballot {
-                Ballot::Aye => {
-                    ensure!(!accounts_aye.contains(&sender), "You have already voted aye.");
-                    // if sender has voted for the other option, remove from the array
-                    if accounts_nay.contains(&sender) {
-                        let i = accounts_nay.iter().position(|x| x == &sender).unwrap() as usize;
-                        accounts_nay.remove(i);
-                    } 
-                    accounts_aye.push(sender.clone());
-                    print("Ballot casted Aye!");
-                }
-                Ballot::Nay => {
-                    ensure!(!accounts_nay.contains(&sender), "You have already voted nay.");
-                    if accounts_aye.contains(&sender) {
-                        let i = accounts_aye.iter().position(|x| x == &sender).unwrap() as usize;
-                        accounts_aye.remove(i);
-                    } 
-                    accounts_nay.push(sender.clone());
-                    print("Ballot casted Nay!");
-                }
-            }
-
-            <VotedAccounts<T>>::insert((reference_index, 0), accounts_aye);
-            <VotedAccounts<T>>::insert((reference_index, 1), accounts_nay);
-            Self::deposit_event(RawEvent::Voted(sender, reference_index, ballot));
-            Ok(())
+            let vote = <VotesByIndex<T>>::get(&reference_index);
+            Self::ensure_can_vote(&vote, &sender)?;
+            ensure!(vote.vote_type == VoteType::Simple, "This vote is not a simple vote. Use the cast function of its vote_type instead!");
+            Self::cast_ballot_f(sender, reference_index, ballot)
         }
 
         fn cast_ballot_with_options(origin, reference_index: ReferenceIndex, option: u8) -> Result {
             let sender = ensure_signed(origin)?;
-            let vote = <VotesByIndex<T>>::get(&reference_index);
-            let now = <system::Module<T>>::block_number();
             ensure!(<VotesByIndex<T>>::exists(&reference_index), "Vote doesn't exists");
-            ensure!(vote.creator != sender, "You cannot vote your own vote.");
-            ensure!(vote.vote_ends > now, "This vote has already been expired.");
-            ensure!(vote.vote_type == 0, "This vote is LockVote. Use 'cast_lockvote' instead!");
-
-            if vote.approved != T::Hash::default() {
-              // fails is the sender's account is not registered for CAHash.
-                certificate::Module::<T>::check_account(sender.clone(), vote.approved)?;
-            }
+            let vote = <VotesByIndex<T>>::get(&reference_index);
+            Self::ensure_can_vote(&vote, &sender)?;
+            ensure!(vote.vote_type == VoteType::MultiOption, "This vote is not a multi-option vote.");
 
             // check if the option is in a valid range
             let options = <VoteOptions>::get(reference_index); // => Vec<Vec<u8>>
-            ensure!(option <= options.len() as u8, "Provided option out of range.");
+            ensure!((option as usize) < options.len(), "Provided option out of range.");
 
-            let mut accounts = <AccountsByOption<T>>::get((&reference_index, &option));
-            let mut voted_option:u8 = 255;
-            if <VotedOption<T>>::exists((&reference_index, &sender)){
-              voted_option = <VotedOption<T>>::get((&reference_index, &sender));
-            }
-
-            // if an option is already registered
-            if voted_option != 255 {
+            // if an option is already registered, record_ballot moves the ballot to the new option
+            if let Some(BallotRecord { ballot: OptionBallot::Single(voted_option), .. }) = <Ballots<T>>::get(reference_index, &sender) {
               ensure!(voted_option != option, "Provided option is already registered."); 
-              
-              // remove sender from accounts
-              let mut prev_accounts = <AccountsByOption<T>>::get((&reference_index, &voted_option));
-              let i = prev_accounts.iter().position(|x| x == &sender).unwrap() as usize;
-              prev_accounts.remove(i);
-              <AccountsByOption<T>>::insert((&reference_index, voted_option), prev_accounts);
-              <VotedOption<T>>::remove((&reference_index, &sender));
               print("Ballot updated with new option!");
             } 
 
-            accounts.push(sender.clone());
-            <AccountsByOption<T>>::insert((&reference_index, &option), accounts);
-            <VotedOption<T>>::insert((reference_index, &sender), option);
+            let ballot = OptionBallot::Single(option);
+            Self::record_ballot(&vote, &sender, BallotRecord { ballot: ballot.clone(), weight: 1 });
 
+            Self::deposit_event(RawEvent::OptionVoted(sender, reference_index, ballot));
             print("Ballot Casted!");
             Ok(())
         }
 
+        // Delegates voting power to `to` on every vote, or only on the vote of `scope`.
+        // The delegate's ballot counts for the delegator too, unless the delegator votes directly.
+        // If the delegate doesn't vote, the power follows its own delegation, up to MaxDelegationDepth delegations.
+        // Not available on Lock, Quadratic and Encrypted votes.
+        // checks:
+            // a: the sender is certified for at least one CAHash
+            // b: for a single vote, the vote is open and the sender is registered for its CAHash
+            // c: the delegation doesn't create a cycle
+            // d: no vote counted over several blocks counts delegations of the scope
+        fn delegate(origin, to: T::AccountId, scope: Option<ReferenceIndex>) -> Result {
+            let sender = ensure_signed(origin)?;
+            ensure!(sender != to, "You cannot delegate to yourself.");
+            ensure!(!certificate::Module::<T>::cahashes_by_account(&sender).is_empty(), "Only certified accounts can delegate.");
+            if let Some(reference_index) = scope {
+                ensure!(<VotesByIndex<T>>::exists(&reference_index), "Vote doesn't exists");
+                let vote = Self::votes(reference_index);
+                ensure!(vote.vote_ends > <system::Module<T>>::block_number(), "This vote has already been expired.");
+                ensure!(Self::delegable(vote.vote_type), "Votes of this vote_type cannot be delegated.");
+                if vote.approved != T::Hash::default() {
+                    certificate::Module::<T>::check_account(sender.clone(), vote.approved)?;
+                }
+            }
+            ensure!(!Self::delegation_reaches(&to, &sender, scope), "Delegation would create a cycle.");
+            ensure!(!Self::counting(scope), "Delegations cannot change while a vote counts them.");
+
+            Self::remove_delegation(&sender, scope);
+            <Delegations<T>>::insert((sender.clone(), scope), to.clone());
+            let count = <DelegatorCount>::get(scope);
+            <Delegators<T>>::insert((scope, count), &sender);
+            <DelegatorIndex<T>>::insert((sender.clone(), scope), count);
+            <DelegatorCount>::insert(scope, count + 1);
+            Self::deposit_event(RawEvent::Delegated(sender, to, scope));
+            Ok(())
+        }
+
+        fn undelegate(origin, scope: Option<ReferenceIndex>) -> Result {
+            let sender = ensure_signed(origin)?;
+            ensure!(!Self::counting(scope), "Delegations cannot change while a vote counts them.");
+            ensure!(Self::remove_delegation(&sender, scope).is_some(), "You have not delegated.");
+            Self::deposit_event(RawEvent::Undelegated(sender, scope));
+            Ok(())
+        }
+
         // conclude a vote given expired
         // anyone can call this function, and Vote.concluded returns true
-        pub fn conclude_vote(_origin, reference_index: u64) -> Result {
-            let vote = <VotesByIndex<T>>::get(&reference_index);
-            // ensure the vote is concluded before tallying
-            ensure!(vote.concluded == false, "This vote has already concluded.");
-            let now = <system::Module<T>>::block_number();
-            // double check
-            ensure!(now > vote.vote_ends, "This vote hasn't been expired yet.");
-            Self::tally(reference_index)?;
-            // For some reason Storage is not reflected, but works.
-            <VotesByIndex<T>>::mutate(&reference_index, |vote| vote.concluded = true);
-            <VoteByCreatorArray<T>>::mutate((vote.creator, &reference_index), |vote| vote.concluded = true);
-            Self::deposit_event(RawEvent::Concluded(reference_index));
-            print("Vote concluded.");
-            Ok(())
+        // votes are also counted in on_finalize from the block after they expire, as far as MaxConclusionWeight allows
+        // max_accounts: accounts the call visits at most. a delegable vote with more is counted in part and
+        // the next call continues. Encrypted votes need one per voter
+        #[weight = ConcludeWeight::<T>(PhantomData)]
+        pub fn conclude_vote(_origin, reference_index: u64, max_accounts: u64) -> Result {
+            Self::conclude(reference_index, max_accounts).map(|_| ())
         }
     }
 }
 
+// weight of visiting accounts while counting
+fn accounts_weight(accounts: u64) -> Weight {
+    CONCLUDE_ACCOUNT_WEIGHT.saturating_mul(accounts.saturated_into::<Weight>())
+}
+
+// weight of counting base + per option * options + per account * accounts
+fn counting_weight(options: usize, accounts: u64) -> Weight {
+    CONCLUDE_OPTION_WEIGHT
+        .saturating_mul(options.saturated_into::<Weight>())
+        .saturating_add(accounts_weight(accounts))
+        .saturating_add(CONCLUDE_BASE_WEIGHT)
+}
+
+// weight of the calls that may conclude a vote: counting its options and accounts and dispatching its proposal
+pub struct ConcludeWeight<T>(PhantomData<T>);
+
+// conclude_vote, from its max_accounts
+impl<T: Trait> WeighData<(&ReferenceIndex, &u64)> for ConcludeWeight<T> {
+    fn weigh_data(&self, (_, max_accounts): (&ReferenceIndex, &u64)) -> Weight {
+        counting_weight(MAX_OPTIONS, *max_accounts).saturating_add(T::MaxProposalWeight::get())
+    }
+}
+
+impl<T: Trait> ClassifyDispatch<(&ReferenceIndex, &u64)> for ConcludeWeight<T> {
+    fn classify_dispatch(&self, _: (&ReferenceIndex, &u64)) -> DispatchClass {
+        DispatchClass::Normal
+    }
+}
+
+// submit_decryption_share, from its max_voters. the discrete log of the decrypted sum is searched up to the number of voters
+impl<T: Trait> WeighData<(&ReferenceIndex, &Point, &DecryptionProof, &u64)> for ConcludeWeight<T> {
+    fn weigh_data(&self, (_, _, _, max_voters): (&ReferenceIndex, &Point, &DecryptionProof, &u64)) -> Weight {
+        counting_weight(2, *max_voters).saturating_add(T::MaxProposalWeight::get())
+    }
+}
+
+impl<T: Trait> ClassifyDispatch<(&ReferenceIndex, &Point, &DecryptionProof, &u64)> for ConcludeWeight<T> {
+    fn classify_dispatch(&self, _: (&ReferenceIndex, &Point, &DecryptionProof, &u64)) -> DispatchClass {
+        DispatchClass::Normal
+    }
+}
+
+// cancel_vote, from its max_accounts
+pub struct CancelWeight;
+
+impl WeighData<(&ReferenceIndex, &u64)> for CancelWeight {
+    fn weigh_data(&self, (_, max_accounts): (&ReferenceIndex, &u64)) -> Weight {
+        CANCEL_ACCOUNT_WEIGHT
+            .saturating_mul((*max_accounts).saturated_into::<Weight>())
+            .saturating_add(CANCEL_BASE_WEIGHT)
+    }
+}
+
+impl ClassifyDispatch<(&ReferenceIndex, &u64)> for CancelWeight {
+    fn classify_dispatch(&self, _: (&ReferenceIndex, &u64)) -> DispatchClass {
+        DispatchClass::Normal
+    }
+}
+
+// reap_vote, from its max_accounts
+pub struct ReapWeight;
+
+impl WeighData<(&ReferenceIndex, &u64)> for ReapWeight {
+    fn weigh_data(&self, (_, max_accounts): (&ReferenceIndex, &u64)) -> Weight {
+        REAP_ACCOUNT_WEIGHT
+            .saturating_mul((*max_accounts).saturated_into::<Weight>())
+            .saturating_add(REAP_BASE_WEIGHT)
+    }
+}
+
+impl ClassifyDispatch<(&ReferenceIndex, &u64)> for ReapWeight {
+    fn classify_dispatch(&self, _: (&ReferenceIndex, &u64)) -> DispatchClass {
+        DispatchClass::Normal
+    }
+}
+
 impl<T: Trait> Module<T> {
-    // keep track of accounts in array by Aye/Nay in <VotedAccounts<T>>
-    // TODO: lockvote_tally should check <LockBalance> for accuracy
-    fn cast_ballot_f(sender: T::AccountId, reference_index: ReferenceIndex, ballot: Ballot) -> Result {
-        let mut accounts_aye = <VotedAccounts<T>>::get((reference_index, 0));
-        let mut accounts_nay = <VotedAccounts<T>>::get((reference_index, 1));
-        // keep track of voter's id in aye or nay vector in Vote
-        // Voter can change his vote b/w aye and nay
-        // Voter cannot vote twice
-        match ballot {
-            Ballot::Aye => {
-                ensure!(!accounts_aye.contains(&sender), "You have already voted aye.");
-                // if sender has voted for the other option, remove from the array
-                if accounts_nay.contains(&sender) {
-                    let i = accounts_nay.iter().position(|x| x == &sender).unwrap() as usize;
-                    accounts_nay.remove(i);
-                } 
-                accounts_aye.push(sender.clone());
-                <VotedAccounts<T>>::insert((reference_index, 0), accounts_aye);
-                print("Ballot casted Aye!");
-            }
-            Ballot::Nay => {
-                ensure!(!accounts_nay.contains(&sender), "You have already voted nay.");
-                if accounts_aye.contains(&sender) {
-                    let i = accounts_aye.iter().position(|x| x == &sender).unwrap() as usize;
-                    accounts_aye.remove(i);
-                } 
-                accounts_nay.push(sender.clone());
-                <VotedAccounts<T>>::insert((reference_index, 1), accounts_nay);
-                print("Ballot casted Nay!");
+    // votes created by the account, oldest first. page 0 holds its first VOTES_PER_PAGE votes
+    pub fn votes_by_creator(account: T::AccountId, page: u64) -> Vec<Vote<T::AccountId, T::BlockNumber, T::Hash>> {
+        let created = <CreatedVoteCount<T>>::get(&account);
+        let first = page.saturating_mul(VOTES_PER_PAGE).saturating_add(1);
+        let last = first.saturating_add(VOTES_PER_PAGE - 1).min(created);
+        (first..=last)
+            .filter_map(|index| <VoteByCreatorArray<T>>::get((account.clone(), index)))
+            .map(Self::votes)
+            .collect()
+    }
+
+    // last block accepting ballots. CommitReveal votes accept reveals until the end of RevealPeriod
+    fn ballots_close(vote: &VoteOf<T>) -> T::BlockNumber {
+        match vote.vote_type {
+            VoteType::CommitReveal => vote.vote_ends + T::RevealPeriod::get(),
+            _ => vote.vote_ends,
+        }
+    }
+
+    // slashes the deposit of an unrevealed commitment
+    fn slash_unrevealed(reference_index: ReferenceIndex, committer: T::AccountId) {
+        if let Some((_, deposit)) = <Commitments<T>>::take((reference_index, &committer)) {
+            let (imbalance, _) = T::Currency::slash_reserved(&committer, deposit);
+            T::Slash::on_unbalanced(imbalance);
+            Self::deposit_event(RawEvent::Unrevealed(committer, reference_index, deposit));
+        }
+    }
+
+    // returns or slashes the creation deposit of the vote, if it still has one
+    fn settle_deposit(reference_index: ReferenceIndex, slash: bool) {
+        if let Some((creator, deposit)) = <CreationDeposits<T>>::take(reference_index) {
+            if slash {
+                let (imbalance, _) = T::Currency::slash_reserved(&creator, deposit);
+                T::Slash::on_unbalanced(imbalance);
+                Self::deposit_event(RawEvent::DepositSlashed(creator, reference_index, deposit));
+            } else {
+                T::Currency::unreserve(&creator, deposit);
+                Self::deposit_event(RawEvent::DepositReturned(creator, reference_index, deposit));
+            }
+        }
+    }
+
+    // Counts up to max_accounts accounts of the vote, and concludes it once all of them are counted.
+    // Returns the accounts visited. Only delegable votes visit accounts besides the voters of Encrypted votes:
+    // the others are concluded from their running totals
+    fn conclude(reference_index: ReferenceIndex, max_accounts: u64) -> rstd::result::Result<u64, &'static str> {
+        let vote = <VotesByIndex<T>>::get(&reference_index);
+        // ensure the vote is concluded before tallying
+        ensure!(vote.concluded == false, "This vote has already concluded.");
+        let now = <system::Module<T>>::block_number();
+        // double check
+        ensure!(now > Self::ballots_close(&vote), "This vote hasn't been expired yet.");
+        let (tally, head_counts, turnout, visited) = match vote.vote_type {
+            // ballots weigh 1, so the totals are the head counts
+            VoteType::Encrypted => {
+                let voters = <VoterCount>::get(reference_index);
+                ensure!(voters <= max_accounts, "The vote has more voters than max_accounts.");
+                let tally = Self::decrypt_tally(reference_index)?;
+                let head_counts = tally.totals.iter().map(|total| (*total).saturated_into::<u64>()).collect();
+                (tally, head_counts, voters, voters)
+            }
+            vote_type if !Self::delegable(vote_type) => {
+                let (totals, head_counts) = Self::running_totals(&vote);
+                (Self::tally(vote_type, totals), head_counts, <VoterCount>::get(reference_index), 0)
+            }
+            vote_type => {
+                let mut counting = <CountProgress>::get(reference_index).unwrap_or_else(|| {
+                    let (totals, head_counts) = Self::running_totals(&vote);
+                    Counting { totals, head_counts, turnout: <VoterCount>::get(reference_index), ..Default::default() }
+                });
+                let visited = Self::count(&vote, &mut counting, max_accounts);
+                if counting.stage != CountStage::Done {
+                    if !<CountProgress>::exists(reference_index) {
+                        <CountingVotes>::mutate(|count| *count += 1);
+                    }
+                    <CountProgress>::insert(reference_index, counting);
+                    Self::deposit_event(RawEvent::CountPending(reference_index));
+                    return Ok(visited);
+                }
+                Self::stop_counting(reference_index);
+                (Self::tally(vote_type, counting.totals), counting.head_counts, counting.turnout, visited)
+            }
+        };
+        let outcome = Self::outcome(&vote, &tally, turnout);
+        let result = VoteResult {
+            tally,
+            head_counts,
+            ballots: <VoterCount>::get(reference_index),
+            turnout,
+            eligible: Self::eligible(&vote),
+            concluded_at: now,
+        };
+        <VoteResults<T>>::insert(reference_index, &result);
+        <VoteOutcomes>::insert(reference_index, outcome);
+        <VotesByIndex<T>>::mutate(&reference_index, |vote| vote.concluded = true);
+        Self::deposit_event(RawEvent::Concluded(reference_index, result.tally, outcome));
+        print("Vote concluded.");
+        Self::settle_deposit(reference_index, outcome == Outcome::QuorumNotMet);
+
+        if let Some(proposal) = <Proposals<T>>::take(reference_index) {
+            if outcome == Outcome::Passed {
+                let succeeded = proposal.dispatch(RawOrigin::Passed(reference_index).into()).is_ok();
+                Self::deposit_event(RawEvent::Executed(reference_index, succeeded));
             }
         }
+        Ok(visited)
+    }
+
+    // drops the CountProgress of a vote counted in part
+    fn stop_counting(reference_index: ReferenceIndex) {
+        if <CountProgress>::take(reference_index).is_some() {
+            <CountingVotes>::mutate(|count| *count = count.saturating_sub(1));
+        }
+    }
+
+    // whether a vote counted in part counts the delegations of the scope
+    fn counting(scope: Option<ReferenceIndex>) -> bool {
+        match scope {
+            Some(reference_index) => <CountProgress>::exists(reference_index),
+            None => <CountingVotes>::get() > 0,
+        }
+    }
+
+    // weight on_finalize gives a step of counting a vote, as the weight of its options and proposal,
+    // and the accounts the step visits at least: every voter of an Encrypted vote, one delegator of a delegable vote,
+    // or one ranked ballot, see count
+    fn conclusion_weight(reference_index: ReferenceIndex) -> (Weight, u64) {
+        let vote = Self::votes(reference_index);
+        let proposal = <Proposals<T>>::get(reference_index).map(|proposal| proposal.get_dispatch_info().weight);
+        let option_count = Self::option_count(&vote);
+        let weight = counting_weight(option_count as usize, 0).saturating_add(proposal.unwrap_or(0));
+        let per_delegator = u64::from(T::MaxDelegationDepth::get()) + 1;
+        let least = match vote.vote_type {
+            VoteType::Encrypted => <VoterCount>::get(reference_index),
+            VoteType::RankedChoice => per_delegator.max(option_count.into()),
+            vote_type if Self::delegable(vote_type) => per_delegator,
+            _ => 0,
+        };
+        (weight, least)
+    }
+
+    // Aye and Nay of an Encrypted vote from the trustees' decryption shares
+    fn decrypt_tally(reference_index: ReferenceIndex) -> rstd::result::Result<Tally, &'static str> {
+        let shares = <DecryptionShares>::get(&reference_index);
+        ensure!(shares.len() >= <TrusteeCommitments>::get(&reference_index).len(), "Waiting for decryption shares from the trustees.");
+        let voters = <VoterCount>::get(&reference_index);
+        let aye = elgamal::combine(&<EncryptedTotals>::get(&reference_index), &shares, voters)?;
+        Ok(Self::tally(VoteType::Encrypted, [aye as VoteWeight, (voters - aye) as VoteWeight].to_vec()))
+    }
+
+    // records an Aye/Nay/Abstain ballot of Simple, Lock and CommitReveal votes
+    // Voter can change his vote b/w them
+    // Voter cannot vote twice
+    // lockvotes weigh deposit * multiplier of the lock in LockBalance
+    fn cast_ballot_f(sender: T::AccountId, reference_index: ReferenceIndex, ballot: Ballot) -> Result {
+        let vote = Self::votes(reference_index);
+        if <Ballots<T>>::get(reference_index, &sender).map(|record| record.ballot) == Some(OptionBallot::Binary(ballot)) {
+            return Err(match ballot {
+                Ballot::Aye => "You have already voted aye.",
+                Ballot::Nay => "You have already voted nay.",
+                Ballot::Abstain => "You have already abstained.",
+            });
+        }
+        let weight = match vote.vote_type {
+            VoteType::Lock => Self::lock_power(&vote, sender.clone()),
+            _ => 1,
+        };
+        Self::record_ballot(&vote, &sender, BallotRecord { ballot: OptionBallot::Binary(ballot), weight });
+        print("Ballot casted!");
         Self::deposit_event(RawEvent::Voted(sender, reference_index, ballot));
         Ok(())
     }
@@ -365,44 +1511,473 @@ impl<T: Trait> Module<T> {
         <VoteCreator<T>>::insert(new_vote_num.clone(), sender.clone());
         <CreatedVoteCount<T>>::insert(sender.clone(), vote_count_by_sender);
         <AllVoteCount>::put(new_vote_num.clone());
-        <VoteByCreatorArray<T>>::insert((sender.clone(), new_vote_num), new_vote);
+        <VoteByCreatorArray<T>>::insert((sender.clone(), vote_count_by_sender), new_vote_num);
 
         Self::deposit_event(RawEvent::Created(sender, new_vote_num));
         print("Vote created!");
         Ok(())
     }
 
-    // only called after the vote expired
-    fn tally(reference_index: u64) -> Result {
-        let vote = Self::votes(reference_index);
-        let mut aye_count: u64 = 0;
-        let mut nay_count: u64 = 0;
+    // checks the parameters each vote_type needs at creation
+    // a vote has 1 to MAX_OPTIONS options
+    fn validate_vote_type(vote_type: VoteType, options: &[Vec<u8>], cert_index: u64) -> Result {
+        ensure!(!options.is_empty(), "At least one option should be provided.");
+        ensure!(options.len() <= MAX_OPTIONS, "Cannot add more than 254 options");
+        match vote_type {
+            VoteType::Simple | VoteType::Lock | VoteType::CommitReveal | VoteType::Encrypted => Ok(()),
+            VoteType::MultiOption => {
+                ensure!(options.len() >= 2, "Multi-option vote needs at least two options.");
+                Ok(())
+            }
+            VoteType::RankedChoice => {
+                ensure!(options.len() >= 2, "Ranked choice vote needs at least two options.");
+                Ok(())
+            }
+            VoteType::Approval => {
+                ensure!(options.len() >= 2, "Approval vote needs at least two options.");
+                Ok(())
+            }
+            VoteType::Score(max_score) => {
+                ensure!(options.len() >= 2, "Score vote needs at least two options.");
+                ensure!(max_score > 0, "Maximum score should be at least 1.");
+                Ok(())
+            }
+            VoteType::Quadratic(_) => {
+                ensure!(options.len() >= 2, "Quadratic vote needs at least two options.");
+                ensure!(cert_index != 0, "Quadratic vote requires a certificate to keep 1 person 1 ballot.");
+                Ok(())
+            }
+        }
+    }
+
+    // Simple, Lock and CommitReveal count Aye, Nay and Abstain whatever VoteOptions holds
+    fn option_count(vote: &VoteOf<T>) -> u8 {
         match vote.vote_type {
-            // normal vote tally
-            0 => {
-                aye_count = <VotedAccounts<T>>::get((reference_index, 0)).len() as u64;
-                nay_count = <VotedAccounts<T>>::get((reference_index, 1)).len() as u64;
-            }
-            // lock vote tally
-            // deposit amount * duration
-            1 => {
-                for account in <VotedAccounts<T>>::get((reference_index, 0)) {
-                    let lock_vote = <LockBalance<T>>::get((reference_index, account));
-                    let vote_power: u64 = lock_vote.deposit.saturated_into::<u64>() * lock_vote.duration.saturated_into::<u64>();
-                    aye_count += vote_power;
+            VoteType::Simple | VoteType::Lock | VoteType::CommitReveal => 3,
+            _ => <VoteOptions>::get(vote.id).len() as u8,
+        }
+    }
+
+    // weight and number of voters counted for each option from the ballots cast directly
+    fn running_totals(vote: &VoteOf<T>) -> (Vec<VoteWeight>, Vec<u64>) {
+        let option_count = Self::option_count(vote);
+        let totals = (0..option_count).map(|option| <OptionTotals>::get((vote.id, option))).collect();
+        let head_counts = (0..option_count).map(|option| <OptionHeadCounts>::get((vote.id, option))).collect();
+        (totals, head_counts)
+    }
+
+    // counts the stages left of a delegable vote, as far as max_accounts allows, and returns the accounts visited.
+    // a committer is 1 account, a delegator MaxDelegationDepth + 1: itself and the delegates it may pass,
+    // and a ranked ballot in a round of instant-runoff 1 per option, as its ranking is searched for a remaining one
+    fn count(vote: &VoteOf<T>, counting: &mut Counting, max_accounts: u64) -> u64 {
+        let per_delegator = u64::from(T::MaxDelegationDepth::get()) + 1;
+        let option_count = Self::option_count(vote);
+        let per_ballot = u64::from(option_count);
+        let mut remaining = tally::remaining(option_count, &counting.rounds);
+        let mut visited = 0u64;
+        loop {
+            match counting.stage {
+                CountStage::Committers => {
+                    if counting.next >= <CommitterCount>::get(vote.id) {
+                        <CommitterCount>::remove(vote.id);
+                        counting.stage = CountStage::VoteDelegators;
+                        counting.next = 0;
+                        continue;
+                    }
+                    if visited >= max_accounts {
+                        break;
+                    }
+                    Self::slash_unrevealed(vote.id, <Committers<T>>::take((vote.id, counting.next)));
+                    visited += 1;
+                }
+                CountStage::VoteDelegators | CountStage::Delegators => {
+                    let scope = if counting.stage == CountStage::VoteDelegators { Some(vote.id) } else { None };
+                    if counting.next >= <DelegatorCount>::get(scope) {
+                        counting.stage = match scope {
+                            Some(_) => CountStage::Delegators,
+                            None if vote.vote_type == VoteType::RankedChoice => {
+                                // ranked ballots add to no running total
+                                counting.totals = (0..option_count).map(|_| 0).collect();
+                                CountStage::Runoff
+                            }
+                            None => CountStage::Done,
+                        };
+                        counting.next = 0;
+                        continue;
+                    }
+                    if visited.saturating_add(per_delegator) > max_accounts {
+                        break;
+                    }
+                    let delegator = <Delegators<T>>::get((scope, counting.next));
+                    // a delegation for the vote overrides a global one, and is counted in VoteDelegators
+                    if scope.is_some() || !<Delegations<T>>::exists((delegator.clone(), Some(vote.id))) {
+                        Self::count_delegator(vote, counting, &delegator);
+                    }
+                    visited += per_delegator;
+                }
+                CountStage::Runoff => {
+                    if counting.next >= <VoterCount>::get(vote.id) {
+                        let totals = rstd::mem::replace(&mut counting.totals, (0..option_count).map(|_| 0).collect());
+                        let (round, winner) = tally::close_round(totals, &remaining);
+                        counting.next = 0;
+                        match round.eliminated {
+                            Some(option) => {
+                                remaining[option as usize] = false;
+                                counting.rounds.push(round);
+                            }
+                            // the result keeps the final round
+                            None => {
+                                counting.totals = round.totals.clone();
+                                counting.rounds.push(round);
+                                let rounds = rstd::mem::replace(&mut counting.rounds, Vec::new());
+                                <RunoffResults>::insert(vote.id, RunoffResult { rounds, winner });
+                                counting.stage = CountStage::Done;
+                            }
+                        }
+                        continue;
+                    }
+                    if visited.saturating_add(per_ballot) > max_accounts {
+                        break;
+                    }
+                    let voter = <Voters<T>>::get((vote.id, counting.next));
+                    if let Some(BallotRecord { ballot: OptionBallot::Ranked(ranking), .. }) = <Ballots<T>>::get(vote.id, &voter) {
+                        if let Some(option) = tally::preferred(&ranking, &remaining) {
+                            // the ballot counts for its voter and the delegators it represents
+                            let weight = VoteWeight::from(<Represented<T>>::get((vote.id, &voter)).saturating_add(1));
+                            let total = &mut counting.totals[option as usize];
+                            *total = total.saturating_add(weight);
+                        }
+                    }
+                    visited += per_ballot;
+                }
+                CountStage::Done => break,
+            }
+            counting.next += 1;
+        }
+        visited
+    }
+
+    // counts the ballot the delegator's power reaches once more for the delegator
+    fn count_delegator(vote: &VoteOf<T>, counting: &mut Counting, delegator: &T::AccountId) {
+        let voter = match Self::voter_reached(vote, delegator) {
+            Some(voter) => voter,
+            None => return,
+        };
+        let record = match <Ballots<T>>::get(vote.id, &voter) {
+            Some(record) => record,
+            None => return,
+        };
+        counting.turnout = counting.turnout.saturating_add(1);
+        for (option, weight) in Self::contribution(vote.vote_type, &record) {
+            if let Some(total) = counting.totals.get_mut(option as usize) {
+                *total = total.saturating_add(weight);
+            }
+        }
+        for option in Self::counted_options(&record) {
+            if let Some(count) = counting.head_counts.get_mut(option as usize) {
+                *count = count.saturating_add(1);
+            }
+        }
+        if vote.vote_type == VoteType::RankedChoice {
+            <Represented<T>>::mutate((vote.id, voter), |count| *count += 1);
+        }
+    }
+
+    // number of accounts holding the certificate the vote requires
+    fn eligible(vote: &VoteOf<T>) -> Option<u64> {
+        if vote.approved == T::Hash::default() {
+            return None;
+        }
+        Some(certificate::Module::<T>::accounts_by_cahash(vote.approved).len() as u64)
+    }
+
+    // Simple, Lock, CommitReveal and Encrypted votes pass on the share of Aye out of Aye and Nay. Abstain only adds to turnout.
+    // Approval votes pass on the share of voters approving the leading option,
+    // other votes on the share of the leading option out of all counted weight. Ties never pass.
+    fn outcome(vote: &VoteOf<T>, result: &Tally, turnout: u64) -> Outcome {
+        let leader_total = result.leader().map(|option| result.totals[option as usize]).unwrap_or(0);
+        let (support, total) = match vote.vote_type {
+            VoteType::Simple | VoteType::Lock | VoteType::CommitReveal | VoteType::Encrypted => {
+                let aye = result.totals.get(0).cloned().unwrap_or(0);
+                let nay = result.totals.get(1).cloned().unwrap_or(0);
+                (aye, aye.saturating_add(nay))
+            }
+            VoteType::Approval => (leader_total, turnout as VoteWeight),
+            _ => (leader_total, result.totals.iter().fold(0, |sum: VoteWeight, total| sum.saturating_add(*total))),
+        };
+        tally::outcome(vote.quorum, turnout, vote.threshold, support, total)
+    }
+
+    // deposit * conviction multiplier of the account's lock on the vote
+    fn lock_power(vote: &VoteOf<T>, account: T::AccountId) -> VoteWeight {
+        let lock_vote = <LockBalance<T>>::get((vote.id, account));
+        let multiplier = Self::conviction(vote, lock_vote.until).unwrap_or(0);
+        Self::lock_weight(lock_vote.deposit, multiplier).saturated_into::<VoteWeight>()
+    }
+
+    // multiplier of the longest Convictions entry a lock until `until` covers. None if it covers none
+    fn conviction(vote: &VoteOf<T>, until: T::BlockNumber) -> Option<u32> {
+        let base = T::LockPeriod::get();
+        let periods = if base.is_zero() {
+            0
+        } else {
+            (until.saturating_sub(vote.vote_ends) / base).saturated_into::<u32>()
+        };
+        T::Convictions::get().iter().rev()
+            .find(|(min_periods, _)| *min_periods <= periods)
+            .map(|(_, multiplier)| *multiplier)
+    }
+
+    // lockvotes cannot last past the longest Convictions entry
+    fn max_lock_until(vote: &VoteOf<T>) -> T::BlockNumber {
+        let periods = T::Convictions::get().last().map(|(periods, _)| *periods).unwrap_or(0);
+        vote.vote_ends.saturating_add(T::LockPeriod::get().saturating_mul(periods.into()))
+    }
+
+    fn lock_weight(deposit: BalanceOf<T>, multiplier: u32) -> BalanceOf<T> {
+        deposit.saturating_mul(multiplier.into())
+    }
+
+    // counting weight of each vote_type: LockTally for weighted ballots, SimpleTally for the others
+    fn weigh(vote_type: VoteType, weight: VoteWeight) -> VoteWeight {
+        match vote_type {
+            VoteType::Lock | VoteType::Quadratic(_) | VoteType::Score(_) => T::LockTally::weigh(weight),
+            _ => T::SimpleTally::weigh(weight),
+        }
+    }
+
+    // Tally of the vote_type from its running totals, by the strategy weigh() counted them with
+    fn tally(vote_type: VoteType, totals: Vec<VoteWeight>) -> Tally {
+        match vote_type {
+            VoteType::Lock | VoteType::Quadratic(_) | VoteType::Score(_) => T::LockTally::tally(totals),
+            _ => T::SimpleTally::tally(totals),
+        }
+    }
+
+    // weight the ballot adds to each option. Ranked ballots are only counted in the Runoff stage of count
+    fn contribution(vote_type: VoteType, record: &BallotRecord) -> Vec<(u8, VoteWeight)> {
+        let ballots: Vec<(u8, VoteWeight)> = match &record.ballot {
+            OptionBallot::Binary(ballot) => [(ballot.slot(), record.weight)].to_vec(),
+            OptionBallot::Single(option) => [(*option, record.weight)].to_vec(),
+            OptionBallot::Approval(approved) => approved.iter().map(|option| (*option, record.weight)).collect(),
+            // each option weighs the score given to it
+            OptionBallot::Score(scores) => scores.iter().enumerate()
+                .map(|(option, score)| (option as u8, (*score as VoteWeight).saturating_mul(record.weight)))
+                .collect(),
+            OptionBallot::Ranked(_) => Vec::new(),
+        };
+        ballots.into_iter().map(|(option, weight)| (option, Self::weigh(vote_type, weight))).collect()
+    }
+
+    // options the ballot counts a voter for in OptionHeadCounts
+    fn counted_options(record: &BallotRecord) -> Vec<u8> {
+        match &record.ballot {
+            OptionBallot::Binary(ballot) => [ballot.slot()].to_vec(),
+            OptionBallot::Single(option) => [*option].to_vec(),
+            OptionBallot::Approval(approved) => approved.clone(),
+            OptionBallot::Score(scores) => scores.iter().enumerate()
+                .filter(|(_, score)| **score > 0)
+                .map(|(option, _)| option as u8)
+                .collect(),
+            OptionBallot::Ranked(ranking) => ranking.iter().take(1).cloned().collect(),
+        }
+    }
+
+    // keeps the latest ballot of the sender and moves its weight in OptionTotals
+    // and its count in OptionHeadCounts from the previous ballot
+    fn record_ballot(vote: &VoteOf<T>, sender: &T::AccountId, record: BallotRecord) {
+        match <Ballots<T>>::get(vote.id, sender) {
+            Some(previous) => {
+                for (option, weight) in Self::contribution(vote.vote_type, &previous) {
+                    <OptionTotals>::mutate((vote.id, option), |total| *total = total.saturating_sub(weight));
                 }
-                for account in <VotedAccounts<T>>::get((reference_index, 0)) {
-                    let lock_vote = <LockBalance<T>>::get((reference_index, account));
-                    let vote_power: u64 = lock_vote.deposit.saturated_into::<u64>() * lock_vote.duration.saturated_into::<u64>();
-                    nay_count += vote_power;
+                for option in Self::counted_options(&previous) {
+                    <OptionHeadCounts>::mutate((vote.id, option), |count| *count = count.saturating_sub(1));
                 }
             }
-            _ => ensure!(vote.vote_type <= 1, "This vote_type is not covered."),
+            None => Self::add_voter(vote.id, sender),
+        }
+        for (option, weight) in Self::contribution(vote.vote_type, &record) {
+            <OptionTotals>::mutate((vote.id, option), |total| *total = total.saturating_add(weight));
+        }
+        for option in Self::counted_options(&record) {
+            <OptionHeadCounts>::mutate((vote.id, option), |count| *count = count.saturating_add(1));
+        }
+        <Ballots<T>>::insert(vote.id, sender, record);
+    }
+
+    fn add_voter(reference_index: ReferenceIndex, voter: &T::AccountId) {
+        let count = <VoterCount>::get(reference_index);
+        <Voters<T>>::insert((reference_index, count), voter);
+        <VoterCount>::insert(reference_index, count + 1);
+    }
+
+    fn voters_of(reference_index: ReferenceIndex) -> Vec<T::AccountId> {
+        (0..<VoterCount>::get(reference_index))
+            .map(|index| <Voters<T>>::get((reference_index, index)))
+            .collect()
+    }
+
+    // Binary Merkle tree hashing (left, right) pairs. the last node of an odd level moves up unpaired.
+    // Leaves are pushed one at a time: frontier[h] holds the root of a full subtree of height h
+    // still waiting for its right sibling
+    fn merkle_push(frontier: &mut Vec<Option<T::Hash>>, leaf: T::Hash) {
+        let mut node = leaf;
+        for pending in frontier.iter_mut() {
+            match pending.take() {
+                Some(left) => node = T::Hashing::hash_of(&(left, node)),
+                None => {
+                    *pending = Some(node);
+                    return;
+                }
+            }
+        }
+        frontier.push(Some(node));
+    }
+
+    // root of the pushed leaves. unpaired subtrees join the first larger one to their left
+    fn merkle_root(frontier: &[Option<T::Hash>]) -> T::Hash {
+        let mut root: Option<T::Hash> = None;
+        for left in frontier.iter().flatten() {
+            root = Some(match root {
+                Some(right) => T::Hashing::hash_of(&(*left, right)),
+                None => *left,
+            });
+        }
+        root.unwrap_or_default()
+    }
+
+    // Lock and Quadratic votes weigh the voter's own balance, so they cannot be delegated.
+    // Encrypted ballots are summed as they are cast, before delegations are resolved
+    fn delegable(vote_type: VoteType) -> bool {
+        match vote_type {
+            VoteType::Lock | VoteType::Quadratic(_) | VoteType::Encrypted => false,
+            _ => true,
+        }
+    }
+
+    fn has_voted(vote: &VoteOf<T>, account: &T::AccountId) -> bool {
+        match vote.vote_type {
+            VoteType::Encrypted => <EncryptedBallots<T>>::exists((vote.id, account)),
+            _ => <Ballots<T>>::exists(vote.id, account),
+        }
+    }
+
+    fn lock_deposit(reference_index: ReferenceIndex, who: &T::AccountId, info: LockInfo<BalanceOf<T>, T::BlockNumber>) {
+        <LockBalance<T>>::insert((reference_index, who), info);
+        <LockedVotes<T>>::mutate(who, |votes| votes.push(reference_index));
+        Self::update_lock(who);
+    }
+
+    // drops the lock entry of the vote. update_lock shrinks the governance lock afterwards
+    fn release_lock(reference_index: ReferenceIndex, who: &T::AccountId) {
+        <LockBalance<T>>::remove((reference_index, who));
+        <LockedVotes<T>>::mutate(who, |votes| votes.retain(|locked| *locked != reference_index));
+        Self::deposit_event(RawEvent::Withdrew(who.clone(), reference_index));
+    }
+
+    // sets GOVERNANCE_LOCK to the largest deposit the account has in LockBalance, or removes it.
+    // deposits on different votes overlap, the same balance backs all of them
+    fn update_lock(who: &T::AccountId) {
+        let required = <LockedVotes<T>>::get(who).iter()
+            .map(|reference_index| <LockBalance<T>>::get((reference_index, who)).deposit)
+            .max()
+            .unwrap_or_else(Zero::zero);
+        if required.is_zero() {
+            T::Currency::remove_lock(GOVERNANCE_LOCK, who);
+        } else {
+            T::Currency::set_lock(
+                GOVERNANCE_LOCK,
+                who,
+                required,
+                u64::max_value().saturated_into::<T::BlockNumber>(),   // released by withdraw and withdraw_all
+                WithdrawReasons::except(WithdrawReason::TransactionPayment),
+            );
+        }
+    }
+
+    // whether any ballot or commitment was cast on the vote
+    fn has_ballots(vote: &VoteOf<T>) -> bool {
+        <VoterCount>::get(vote.id) > 0 || <CommitterCount>::get(vote.id) > 0
+    }
+
+    // checks of every ballot: the sender is not the creator, the vote is open,
+    // and the sender is registered for the CAHash of the vote if it requires one
+    fn ensure_can_vote(vote: &VoteOf<T>, sender: &T::AccountId) -> Result {
+        let now = <system::Module<T>>::block_number();
+        ensure!(vote.creator != *sender, "You cannot vote your own vote.");
+        ensure!(now >= vote.starts_at, "This vote hasn't opened yet.");
+        ensure!(vote.vote_ends > now, "This vote has already been expired.");
+        ensure!(!vote.concluded, "This vote has been cancelled.");
+        if vote.approved != T::Hash::default() {
+            certificate::Module::<T>::check_account(sender.clone(), vote.approved)?;
         }
-        let mut result:Vec<u64> = Vec::new();
-        result.push(aye_count);
-        result.push(nay_count);
-        <VoteResults>::insert(reference_index, result);
         Ok(())
     }
-}
\ No newline at end of file
+
+    fn is_eligible(vote: &VoteOf<T>, account: &T::AccountId) -> bool {
+        vote.approved == T::Hash::default()
+            || certificate::Module::<T>::check_account(account.clone(), vote.approved).is_ok()
+    }
+
+    // account the delegator's power goes to on this vote. a delegation for the vote overrides a global one
+    fn delegate_for(delegator: &T::AccountId, reference_index: ReferenceIndex) -> Option<T::AccountId> {
+        <Delegations<T>>::get((delegator.clone(), Some(reference_index)))
+            .or_else(|| <Delegations<T>>::get((delegator.clone(), None)))
+    }
+
+    // Direct voter the power of an eligible delegator goes to: the first voter its delegation chain
+    // reaches within MaxDelegationDepth delegations. None if the delegator votes itself,
+    // so a chain ending in a cycle counts for no one either
+    fn voter_reached(vote: &VoteOf<T>, delegator: &T::AccountId) -> Option<T::AccountId> {
+        if Self::has_voted(vote, delegator) || !Self::is_eligible(vote, delegator) {
+            return None;
+        }
+        let mut current = delegator.clone();
+        for _ in 0..T::MaxDelegationDepth::get() {
+            current = Self::delegate_for(&current, vote.id)?;
+            if Self::has_voted(vote, &current) {
+                return Some(current);
+            }
+        }
+        None
+    }
+
+    // true if following delegations from `from` for the scope reaches `to` within MaxDelegationDepth delegations.
+    // a longer chain counts for no one, so it cannot close a cycle that counts
+    fn delegation_reaches(from: &T::AccountId, to: &T::AccountId, scope: Option<ReferenceIndex>) -> bool {
+        let mut current = from.clone();
+        for _ in 0..T::MaxDelegationDepth::get() {
+            if &current == to {
+                return true;
+            }
+            let next = match scope {
+                Some(reference_index) => Self::delegate_for(&current, reference_index),
+                None => <Delegations<T>>::get((current.clone(), None)),
+            };
+            match next {
+                Some(account) => current = account,
+                None => return false,
+            }
+        }
+        &current == to
+    }
+
+    fn remove_delegation(delegator: &T::AccountId, scope: Option<ReferenceIndex>) -> Option<T::AccountId> {
+        let delegate = <Delegations<T>>::take((delegator.clone(), scope))?;
+        // the last delegator of the scope takes the index of the removed one
+        let index = <DelegatorIndex<T>>::take((delegator.clone(), scope));
+        let last = <DelegatorCount>::get(scope) - 1;
+        let moved = <Delegators<T>>::take((scope, last));
+        if index != last {
+            <Delegators<T>>::insert((scope, index), &moved);
+            <DelegatorIndex<T>>::insert((moved, scope), index);
+        }
+        if last == 0 {
+            <DelegatorCount>::remove(scope);
+        } else {
+            <DelegatorCount>::insert(scope, last);
+        }
+        Some(delegate)
+    }
+}
@@ -0,0 +1,269 @@
+// Exponential ElGamal over Ristretto for Encrypted votes.
+// A ballot encrypts m (1 for Aye, 0 for Nay) to the trustees' joint public key PK as
+// (r·G, m·G + r·PK). Ciphertexts add up component-wise, so the module sums the ballots
+// without decrypting them and the trustees only ever decrypt the total.
+// Trustees hold Shamir shares of the secret key. Whoever deals the shares (or a DKG) publishes
+// Feldman commitments C_j = a_j·G to the coefficients of the sharing polynomial:
+// C_0 is the joint public key and trustee i (1-based) holds the key PK_i = Σ i^j·C_j.
+// Any `threshold` (= number of commitments) trustees decrypt the total together.
+
+use codec::{Encode, Decode};
+use rstd::prelude::Vec;
+use sr_primitives::traits::{BlakeTwo256, Hash};
+use curve25519_dalek::{
+    constants::RISTRETTO_BASEPOINT_POINT,
+    ristretto::{CompressedRistretto, RistrettoPoint},
+    scalar::Scalar,
+    traits::Identity,
+};
+
+// compressed Ristretto point
+pub type Point = [u8; 32];
+// canonical little endian encoding of a scalar
+pub type ScalarBytes = [u8; 32];
+
+const LABEL: &[u8] = b"m-governance/elgamal/v1";
+
+// (r·G, m·G + r·PK). Default is the encryption of 0 with r = 0, the neutral element of add
+#[derive(Clone, Copy, PartialEq, Eq, Encode, Decode, Default)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct Ciphertext {
+    pub a: Point,
+    pub b: Point,
+}
+
+// Disjunctive Chaum-Pedersen proof that a ciphertext encrypts 0 or 1.
+// Branch k proves log_G(a) == log_PK(b - k·G). The challenge binds the proof to a context,
+// so a ballot cannot be copied to another vote or voter.
+#[derive(Clone, PartialEq, Eq, Encode, Decode, Default)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct BallotProof {
+    pub c0: ScalarBytes,
+    pub c1: ScalarBytes,
+    pub z0: ScalarBytes,
+    pub z1: ScalarBytes,
+}
+
+// Chaum-Pedersen proof that a decryption share D = s_i·a uses the secret behind PK_i = s_i·G
+#[derive(Clone, PartialEq, Eq, Encode, Decode, Default)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct DecryptionProof {
+    pub c: ScalarBytes,
+    pub z: ScalarBytes,
+}
+
+fn point(bytes: &Point) -> Result<RistrettoPoint, &'static str> {
+    CompressedRistretto(*bytes).decompress().ok_or("Invalid curve point.")
+}
+
+fn scalar(bytes: &ScalarBytes) -> Result<Scalar, &'static str> {
+    Scalar::from_canonical_bytes(*bytes).ok_or("Invalid scalar.")
+}
+
+// hashes the label, context and points to a scalar. 512 bits of hash keep the reduction unbiased
+fn challenge(context: &[u8], points: &[&RistrettoPoint]) -> Scalar {
+    let mut transcript = Vec::with_capacity(LABEL.len() + context.len() + points.len() * 32 + 1);
+    transcript.push(0);
+    transcript.extend_from_slice(LABEL);
+    transcript.extend_from_slice(context);
+    for point in points {
+        transcript.extend_from_slice(point.compress().as_bytes());
+    }
+    let mut wide = [0u8; 64];
+    wide[..32].copy_from_slice(BlakeTwo256::hash(&transcript).as_ref());
+    transcript[0] = 1;
+    wide[32..].copy_from_slice(BlakeTwo256::hash(&transcript).as_ref());
+    Scalar::from_bytes_mod_order_wide(&wide)
+}
+
+impl Ciphertext {
+    pub fn add(&self, other: &Ciphertext) -> Result<Ciphertext, &'static str> {
+        Ok(Ciphertext {
+            a: (point(&self.a)? + point(&other.a)?).compress().to_bytes(),
+            b: (point(&self.b)? + point(&other.b)?).compress().to_bytes(),
+        })
+    }
+
+    pub fn sub(&self, other: &Ciphertext) -> Result<Ciphertext, &'static str> {
+        Ok(Ciphertext {
+            a: (point(&self.a)? - point(&other.a)?).compress().to_bytes(),
+            b: (point(&self.b)? - point(&other.b)?).compress().to_bytes(),
+        })
+    }
+}
+
+pub fn verify_ballot(public_key: &Point, ciphertext: &Ciphertext, proof: &BallotProof, context: &[u8]) -> Result<(), &'static str> {
+    let pk = point(public_key)?;
+    let a = point(&ciphertext.a)?;
+    let b = point(&ciphertext.b)?;
+    let (c0, c1) = (scalar(&proof.c0)?, scalar(&proof.c1)?);
+    let (z0, z1) = (scalar(&proof.z0)?, scalar(&proof.z1)?);
+    let b1 = b - RISTRETTO_BASEPOINT_POINT;
+    // commitments each branch must have used
+    let t0 = (z0 * RISTRETTO_BASEPOINT_POINT - c0 * a, z0 * pk - c0 * b);
+    let t1 = (z1 * RISTRETTO_BASEPOINT_POINT - c1 * a, z1 * pk - c1 * b1);
+    let c = challenge(context, &[&pk, &a, &b, &t0.0, &t0.1, &t1.0, &t1.1]);
+    if c0 + c1 == c {
+        Ok(())
+    } else {
+        Err("Invalid ballot proof.")
+    }
+}
+
+// PK_i = Σ index^j·C_j
+pub fn verification_key(commitments: &[Point], index: u64) -> Result<Point, &'static str> {
+    let x = Scalar::from(index);
+    let mut power = Scalar::one();
+    let mut key = RistrettoPoint::identity();
+    for commitment in commitments {
+        key += power * point(commitment)?;
+        power *= x;
+    }
+    Ok(key.compress().to_bytes())
+}
+
+pub fn verify_decryption_share(key: &Point, ciphertext: &Ciphertext, share: &Point, proof: &DecryptionProof) -> Result<(), &'static str> {
+    let key = point(key)?;
+    let a = point(&ciphertext.a)?;
+    let share = point(share)?;
+    let (c, z) = (scalar(&proof.c)?, scalar(&proof.z)?);
+    let t1 = z * RISTRETTO_BASEPOINT_POINT - c * key;
+    let t2 = z * a - c * share;
+    if challenge(&[], &[&key, &a, &share, &t1, &t2]) == c {
+        Ok(())
+    } else {
+        Err("Invalid decryption share proof.")
+    }
+}
+
+// Lagrange coefficient at 0 of `index` among `indices`
+fn lagrange(index: u64, indices: &[u64]) -> Scalar {
+    let x = Scalar::from(index);
+    let mut numerator = Scalar::one();
+    let mut denominator = Scalar::one();
+    for other in indices.iter().filter(|other| **other != index) {
+        let y = Scalar::from(*other);
+        numerator *= y;
+        denominator *= y - x;
+    }
+    numerator * denominator.invert()
+}
+
+// Decrypts the ciphertext with verified shares given as (trustee index, share) of distinct trustees.
+// m·G = b - Σ λ_i·D_i, and m is searched up to `max`, the number of ballots summed.
+pub fn combine(ciphertext: &Ciphertext, shares: &[(u64, Point)], max: u64) -> Result<u64, &'static str> {
+    let indices: Vec<u64> = shares.iter().map(|(index, _)| *index).collect();
+    let mut target = point(&ciphertext.b)?;
+    for (index, share) in shares {
+        target -= lagrange(*index, &indices) * point(share)?;
+    }
+    let mut candidate = RistrettoPoint::identity();
+    for m in 0..=max {
+        if candidate == target {
+            return Ok(m);
+        }
+        candidate += RISTRETTO_BASEPOINT_POINT;
+    }
+    Err("Decryption shares don't open the encrypted tally.")
+}
+
+// Client side: encrypts a ballot and proves it holds 0 or 1.
+// r, w, fake_c and fake_z must be fresh uniformly random scalars.
+#[cfg(feature = "std")]
+pub fn encrypt_ballot(public_key: &Point, aye: bool, context: &[u8], r: Scalar, w: Scalar, fake_c: Scalar, fake_z: Scalar)
+    -> Result<(Ciphertext, BallotProof), &'static str>
+{
+    let pk = point(public_key)?;
+    let m = if aye { Scalar::one() } else { Scalar::zero() };
+    let a = r * RISTRETTO_BASEPOINT_POINT;
+    let b = m * RISTRETTO_BASEPOINT_POINT + r * pk;
+    // the branch that is not true is simulated from fake_c and fake_z
+    let fake_b = if aye { b } else { b - RISTRETTO_BASEPOINT_POINT };
+    let real = (w * RISTRETTO_BASEPOINT_POINT, w * pk);
+    let fake = (fake_z * RISTRETTO_BASEPOINT_POINT - fake_c * a, fake_z * pk - fake_c * fake_b);
+    let (t0, t1) = if aye { (fake, real) } else { (real, fake) };
+    let c = challenge(context, &[&pk, &a, &b, &t0.0, &t0.1, &t1.0, &t1.1]);
+    let real_c = c - fake_c;
+    let real_z = w + real_c * r;
+    let (c0, c1, z0, z1) = if aye { (fake_c, real_c, fake_z, real_z) } else { (real_c, fake_c, real_z, fake_z) };
+    let ciphertext = Ciphertext { a: a.compress().to_bytes(), b: b.compress().to_bytes() };
+    let proof = BallotProof { c0: c0.to_bytes(), c1: c1.to_bytes(), z0: z0.to_bytes(), z1: z1.to_bytes() };
+    Ok((ciphertext, proof))
+}
+
+// Trustee side: share of the decryption of the ciphertext with the secret share s_i. w must be fresh and random
+#[cfg(feature = "std")]
+pub fn decryption_share(secret: Scalar, ciphertext: &Ciphertext, w: Scalar) -> Result<(Point, DecryptionProof), &'static str> {
+    let key = secret * RISTRETTO_BASEPOINT_POINT;
+    let a = point(&ciphertext.a)?;
+    let share = secret * a;
+    let t1 = w * RISTRETTO_BASEPOINT_POINT;
+    let t2 = w * a;
+    let c = challenge(&[], &[&key, &a, &share, &t1, &t2]);
+    let z = w + c * secret;
+    Ok((share.compress().to_bytes(), DecryptionProof { c: c.to_bytes(), z: z.to_bytes() }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // polynomial 7 + 3x + 5x^2: threshold 3
+    fn sharing() -> (Vec<Point>, Vec<Scalar>) {
+        let coefficients = [Scalar::from(7u64), Scalar::from(3u64), Scalar::from(5u64)];
+        let commitments = coefficients.iter().map(|a| (a * RISTRETTO_BASEPOINT_POINT).compress().to_bytes()).collect();
+        let secrets = (1..=5u64).map(|i| {
+            let x = Scalar::from(i);
+            coefficients[0] + coefficients[1] * x + coefficients[2] * x * x
+        }).collect();
+        (commitments, secrets)
+    }
+
+    fn nonces(seed: u64) -> (Scalar, Scalar, Scalar, Scalar) {
+        let s = |n: u64| Scalar::from(seed * 1_000 + n).invert();
+        (s(1), s(2), s(3), s(4))
+    }
+
+    #[test]
+    fn ballot_proofs() {
+        let (commitments, _) = sharing();
+        let pk = commitments[0];
+        for (seed, aye) in [(1, true), (2, false)].iter() {
+            let (r, w, c, z) = nonces(*seed);
+            let (ciphertext, proof) = encrypt_ballot(&pk, *aye, b"vote 1", r, w, c, z).unwrap();
+            assert_eq!(verify_ballot(&pk, &ciphertext, &proof, b"vote 1"), Ok(()));
+            assert!(verify_ballot(&pk, &ciphertext, &proof, b"vote 2").is_err());
+            // a ballot for 2 would need both branches to be simulated
+            let doubled = ciphertext.add(&ciphertext).unwrap();
+            assert!(verify_ballot(&pk, &doubled, &proof, b"vote 1").is_err());
+        }
+    }
+
+    #[test]
+    fn threshold_decryption_of_the_sum() {
+        let (commitments, secrets) = sharing();
+        let pk = commitments[0];
+        let ballots = [true, false, true, true, false];
+        let mut total = Ciphertext::default();
+        for (seed, aye) in ballots.iter().enumerate() {
+            let (r, w, c, z) = nonces(seed as u64 + 1);
+            let (ciphertext, _) = encrypt_ballot(&pk, *aye, b"", r, w, c, z).unwrap();
+            total = total.add(&ciphertext).unwrap();
+        }
+
+        // trustees 2, 4 and 5 decrypt
+        let mut shares = Vec::new();
+        for index in [2u64, 4, 5].iter() {
+            let key = verification_key(&commitments, *index).unwrap();
+            let (share, proof) = decryption_share(secrets[*index as usize - 1], &total, Scalar::from(*index + 40)).unwrap();
+            assert_eq!(verify_decryption_share(&key, &total, &share, &proof), Ok(()));
+            // the share doesn't verify against another trustee's key
+            let other = verification_key(&commitments, 1).unwrap();
+            assert!(verify_decryption_share(&other, &total, &share, &proof).is_err());
+            shares.push((*index, share));
+        }
+        assert_eq!(combine(&total, &shares, ballots.len() as u64), Ok(3));
+        // too few shares don't decrypt
+        assert!(combine(&total, &shares[..2], ballots.len() as u64).is_err());
+    }
+}
@@ -0,0 +1,224 @@
+// Migrations of governance storage written by older runtimes.
+// StorageVersion records the layout of the stored data. on_initialize calls on_runtime_upgrade
+// in the first block of a runtime with a newer STORAGE_VERSION, which brings the storage
+// up to date one version at a time. Chains started with this runtime are at STORAGE_VERSION from genesis.
+//
+// Version 0 is the layout of the first testnet runtime:
+//  - Vote had a u8 vote_type, 0 Simple, 1 Lock and 2 MultiOption, and no starts_at, quorum or threshold
+//  - VoteResults held [aye, nay] as Vec<u64>
+//  - ballots were lists of voters in VotedAccounts (Aye: 0, Nay: 1) and AccountsByOption, besides VotedOption.
+//    cast_ballot_with_options only took votes of vote_type 0, and accepted options.len() as an option.
+//    A vote of vote_type 0 with option ballots becomes MultiOption, dropping its Aye and Nay ballots
+//    and the ballots for options.len(), which has no option to count for
+//  - VoteByCreatorArray held a copy of each Vote under (creator, reference_index)
+//  - each lockvote had a lock of its own, with the reference_index as lock id. LockInfo kept its layout
+// Version 1 is the layout from ballots in a double map until typed vote results:
+//  - VoteResults held the Tally of the vote
+//  - there were no OptionHeadCounts
+//  - Committers held a Vec of the committers of each vote, and commitments hashed (Ballot, salt)
+// Version 2 is the current layout.
+
+use super::*;
+use support::storage::unhashed;
+use rstd::collections::btree_map::BTreeMap;
+
+pub const STORAGE_VERSION: u32 = 2;
+
+pub fn on_runtime_upgrade<T: Trait>() {
+    if <StorageVersion>::get() < 1 {
+        migrate_to_v1::<T>();
+    }
+    if <StorageVersion>::get() < 2 {
+        migrate_to_v2::<T>();
+    }
+    <StorageVersion>::put(STORAGE_VERSION);
+}
+
+#[derive(Encode, Decode)]
+pub struct VoteV0<AccountId, BlockNumber, Hash> {
+    pub id: u64,
+    pub vote_type: u8,
+    pub approved: Hash,
+    pub creator: AccountId,
+    pub when: BlockNumber,
+    pub vote_ends: BlockNumber,
+    pub concluded: bool,
+}
+
+// key of an entry of a map this module no longer declares
+pub fn legacy_key<K: Encode>(storage: &[u8], key: &K) -> [u8; 32] {
+    let mut final_key = b"GovernanceModule ".to_vec();
+    final_key.extend_from_slice(storage);
+    key.encode_to(&mut final_key);
+    runtime_io::blake2_256(&final_key)
+}
+
+fn migrate_to_v1<T: Trait>() {
+    for reference_index in 1..=<AllVoteCount>::get() {
+        let old = match unhashed::get::<VoteV0<T::AccountId, T::BlockNumber, T::Hash>>(&<VotesByIndex<T>>::hashed_key_for(reference_index)) {
+            Some(old) => old,
+            None => continue,
+        };
+        let option_count = <VoteOptions>::get(reference_index).len() as u8;
+        let option_ballots = if old.vote_type == 0 { take_option_ballots::<T>(reference_index, option_count) } else { Vec::new() };
+        let vote = Vote {
+            id: old.id,
+            vote_type: match old.vote_type {
+                1 => VoteType::Lock,
+                2 => VoteType::MultiOption,
+                _ if !option_ballots.is_empty() => VoteType::MultiOption,
+                _ => VoteType::Simple,
+            },
+            approved: old.approved,
+            creator: old.creator,
+            when: old.when,
+            starts_at: old.when,
+            vote_ends: old.vote_ends,
+            concluded: old.concluded,
+            quorum: 0,
+            threshold: Threshold::SimpleMajority,
+        };
+        <VotesByIndex<T>>::insert(reference_index, &vote);
+        migrate_ballots::<T>(&vote, option_ballots);
+        let result_key = <VoteResults<T>>::hashed_key_for(reference_index);
+        if let Some(totals) = unhashed::get::<Vec<u64>>(&result_key) {
+            // the stored [aye, nay] of a vote that became MultiOption is replaced by its option totals
+            let totals = match vote.vote_type {
+                VoteType::MultiOption => (0..option_count).map(|option| <OptionTotals>::get((reference_index, option))).collect(),
+                _ => totals.into_iter().map(VoteWeight::from).collect(),
+            };
+            unhashed::put(&result_key, &Tally { totals });
+        }
+    }
+    migrate_creator_index::<T>();
+    <StorageVersion>::put(1);
+}
+
+// commitments of version 1 no longer open: they are dropped with their deposit returned, so their voters
+// can commit again while the vote is open.
+// counts the voters of each option in OptionHeadCounts, and records the Tally of concluded votes
+// as a VoteResult. Votes concluded before are given ballots_close + 1 as concluded_at, the block
+// on_finalize concludes them in. Their delegations are not resolved again: head counts and turnout
+// count the ballots cast directly. Reaped votes keep no ballots: their head counts are empty
+// and their archive gives ballots and turnout
+fn migrate_to_v2<T: Trait>() {
+    for reference_index in 1..=<AllVoteCount>::get() {
+        if !<VotesByIndex<T>>::exists(reference_index) {
+            continue;
+        }
+        let committers = unhashed::take::<Vec<T::AccountId>>(&legacy_key(b"Committers", &reference_index));
+        for committer in committers.unwrap_or_default() {
+            if let Some((_, deposit)) = <Commitments<T>>::take((reference_index, &committer)) {
+                T::Currency::unreserve(&committer, deposit);
+            }
+        }
+
+        let vote = <Module<T>>::votes(reference_index);
+        let mut counts: Vec<u64> = Vec::new();
+        counts.resize(<Module<T>>::option_count(&vote) as usize, 0);
+        for voter in <Module<T>>::voters_of(reference_index) {
+            if let Some(record) = <Ballots<T>>::get(reference_index, &voter) {
+                for option in <Module<T>>::counted_options(&record) {
+                    if let Some(count) = counts.get_mut(option as usize) {
+                        *count += 1;
+                    }
+                }
+            }
+        }
+        // inserted, not added: record_ballot counted the ballots migrated from version 0 already
+        for (option, count) in counts.iter().enumerate() {
+            if count > 0 {
+                <OptionHeadCounts>::insert((reference_index, option as u8), *count);
+            }
+        }
+
+        let result_key = <VoteResults<T>>::hashed_key_for(reference_index);
+        let tally = match unhashed::get::<Tally>(&result_key) {
+            Some(tally) => tally,
+            None => continue,
+        };
+        let (head_counts, ballots, turnout) = match <VoteArchives<T>>::get(reference_index) {
+            Some(archive) => (Vec::new(), archive.ballots, archive.ballots),
+            None if vote.vote_type == VoteType::Encrypted => (
+                tally.totals.iter().map(|total| (*total).saturated_into::<u64>()).collect(),
+                <VoterCount>::get(reference_index),
+                <VoterCount>::get(reference_index),
+            ),
+            None => (counts, <VoterCount>::get(reference_index), <VoterCount>::get(reference_index)),
+        };
+        <VoteResults<T>>::insert(reference_index, VoteResult {
+            tally,
+            head_counts,
+            ballots,
+            turnout,
+            eligible: <Module<T>>::eligible(&vote),
+            concluded_at: <Module<T>>::ballots_close(&vote) + One::one(),
+        });
+    }
+}
+
+// (voter, option) of the voters in AccountsByOption, cleared with VotedOption.
+// option_count itself is read, as cast_ballot_with_options accepted it
+fn take_option_ballots<T: Trait>(reference_index: ReferenceIndex, option_count: u8) -> Vec<(T::AccountId, u8)> {
+    let mut ballots = Vec::new();
+    for option in 0..=option_count {
+        let voters = unhashed::take::<Vec<T::AccountId>>(&legacy_key(b"AccountsByOption", &(reference_index, option)));
+        for voter in voters.unwrap_or_default() {
+            unhashed::kill(&legacy_key(b"VotedOption", &(reference_index, &voter)));
+            ballots.push((voter, option));
+        }
+    }
+    ballots
+}
+
+// records the voters of VotedAccounts and the option ballots in Ballots,
+// and moves the lock of each lockvote into GOVERNANCE_LOCK
+fn migrate_ballots<T: Trait>(vote: &VoteOf<T>, option_ballots: Vec<(T::AccountId, u8)>) {
+    let reference_index = vote.id;
+    if vote.vote_type == VoteType::MultiOption {
+        let option_count = <VoteOptions>::get(reference_index).len();
+        for (voter, option) in option_ballots {
+            if (option as usize) < option_count {
+                <Module<T>>::record_ballot(vote, &voter, BallotRecord { ballot: OptionBallot::Single(option), weight: 1 });
+            }
+        }
+        for ballot in [Ballot::Aye, Ballot::Nay].iter() {
+            unhashed::kill(&legacy_key(b"VotedAccounts", &(reference_index, ballot.slot())));
+        }
+        return;
+    }
+    for ballot in [Ballot::Aye, Ballot::Nay].iter() {
+        let voters = unhashed::take::<Vec<T::AccountId>>(&legacy_key(b"VotedAccounts", &(reference_index, ballot.slot())));
+        for voter in voters.unwrap_or_default() {
+            let locked = vote.vote_type == VoteType::Lock && <LockBalance<T>>::exists((reference_index, &voter));
+            let weight = if locked { <Module<T>>::lock_power(vote, voter.clone()) } else { 1 };
+            <Module<T>>::record_ballot(vote, &voter, BallotRecord { ballot: OptionBallot::Binary(*ballot), weight });
+            if locked {
+                T::Currency::remove_lock(reference_index.to_be_bytes(), &voter);
+                <LockedVotes<T>>::mutate(&voter, |votes| votes.push(reference_index));
+                <Module<T>>::update_lock(&voter);
+            }
+        }
+    }
+}
+
+// VoteByCreatorArray held a copy of each Vote under (creator, reference_index).
+// Replaces the copies with (creator, n) => reference_index, n counting the creator's votes from 1.
+// Votes are numbered in creation order, so the nth vote of a creator is its nth in VotesByIndex.
+pub fn migrate_creator_index<T: Trait>() {
+    let count = <AllVoteCount>::get();
+    // the copies are dropped first, as a copy and a pointer can share a key
+    for reference_index in 1..=count {
+        if let Some(creator) = <VoteCreator<T>>::get(reference_index) {
+            <VoteByCreatorArray<T>>::remove((creator, reference_index));
+        }
+    }
+    let mut created: BTreeMap<T::AccountId, u64> = BTreeMap::new();
+    for reference_index in 1..=count {
+        if let Some(creator) = <VoteCreator<T>>::get(reference_index) {
+            let index = created.entry(creator.clone()).or_insert(0);
+            *index += 1;
+            <VoteByCreatorArray<T>>::insert((creator, *index), reference_index);
+        }
+    }
+}
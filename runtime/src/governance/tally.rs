@@ -0,0 +1,287 @@
+// Counting methods for the governance module.
+// The module keeps a running total per option, adding the weight the TallyStrategy configured in
+// governance::Trait gives each ballot as it is cast. At conclusion the strategy turns the totals
+// into the Tally, so a runtime can plug in its own counting method.
+// Simple and Lock votes use option 0 for Aye, 1 for Nay and 2 for Abstain.
+
+use codec::{Encode, Decode};
+use rstd::prelude::Vec;
+use sr_primitives::Perbill;
+
+// weight a single ballot carries into the tally
+pub type VoteWeight = u128;
+
+// Typed result of a tally. totals[i] is the weight counted for option i.
+#[derive(Clone, PartialEq, Eq, Encode, Decode, Default)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct Tally {
+    pub totals: Vec<VoteWeight>,
+}
+
+impl Tally {
+    // option with the highest total. None if nothing was counted or the top is tied.
+    pub fn leader(&self) -> Option<u8> {
+        let max = self.totals.iter().max().cloned().unwrap_or(0);
+        if max == 0 || self.totals.iter().filter(|total| **total == max).count() > 1 {
+            return None;
+        }
+        self.totals.iter().position(|total| *total == max).map(|option| option as u8)
+    }
+}
+
+// Share of the counted weight the leading choice needs to pass
+#[derive(Clone, Copy, PartialEq, Eq, Encode, Decode)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub enum Threshold {
+    SimpleMajority,         // more than half
+    SuperMajority(Perbill), // at least the given share
+    Unanimous,              // all of it
+}
+
+impl Default for Threshold {
+    fn default() -> Self {
+        Threshold::SimpleMajority
+    }
+}
+
+impl Threshold {
+    // a super majority of half or less could pass a vote its opposition wins
+    pub fn is_valid(&self) -> bool {
+        match self {
+            Threshold::SuperMajority(share) => *share > Perbill::from_percent(50),
+            _ => true,
+        }
+    }
+
+    pub fn approves(&self, support: VoteWeight, total: VoteWeight) -> bool {
+        if total == 0 || support > total {
+            return false;
+        }
+        match self {
+            Threshold::SimpleMajority => support > total - support,
+            // compares the opposition to the share it may have, so rounding down never lets a vote pass
+            Threshold::SuperMajority(share) => {
+                let opposition = Perbill::from_parts(1_000_000_000 - share.deconstruct());
+                total - support <= opposition * total
+            }
+            Threshold::Unanimous => support == total,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Encode, Decode)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub enum Outcome {
+    Passed,
+    Rejected,
+    QuorumNotMet,
+    Cancelled, // by cancel_vote, never tallied
+}
+
+// quorum and turnout are numbers of voters
+pub fn outcome(quorum: u64, turnout: u64, threshold: Threshold, support: VoteWeight, total: VoteWeight) -> Outcome {
+    if turnout < quorum {
+        Outcome::QuorumNotMet
+    } else if threshold.approves(support, total) {
+        Outcome::Passed
+    } else {
+        Outcome::Rejected
+    }
+}
+
+pub trait TallyStrategy {
+    // what a ballot of the given weight adds to the total of its option
+    fn weigh(weight: VoteWeight) -> VoteWeight;
+
+    // result of a vote from the running totals of its options, totals[i] summing weigh() of each ballot for option i
+    fn tally(totals: Vec<VoteWeight>) -> Tally {
+        Tally { totals }
+    }
+}
+
+// 1 ballot 1 vote. Weight is ignored.
+pub struct SimpleMajority;
+
+impl TallyStrategy for SimpleMajority {
+    fn weigh(_weight: VoteWeight) -> VoteWeight {
+        1
+    }
+}
+
+// Sums the weight of each ballot, e.g. deposit * multiplier of a lockvote.
+pub struct LockWeighted;
+
+impl TallyStrategy for LockWeighted {
+    fn weigh(weight: VoteWeight) -> VoteWeight {
+        weight
+    }
+}
+
+// One counting round of instant-runoff.
+// totals[i] is the number of ballots whose highest ranked remaining option is i.
+#[derive(Clone, PartialEq, Eq, Encode, Decode, Default)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct RunoffRound {
+    pub totals: Vec<VoteWeight>,
+    pub eliminated: Option<u8>, // None in the final round
+}
+
+#[derive(Clone, PartialEq, Eq, Encode, Decode, Default)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct RunoffResult {
+    pub rounds: Vec<RunoffRound>,
+    pub winner: Option<u8>,
+}
+
+// Instant-runoff over ranked ballots, each an ordered list of options, most preferred first, and its weight.
+// Every round counts each ballot for its highest ranked option still in the race, see close_round.
+pub fn instant_runoff(option_count: u8, ballots: &[(Vec<u8>, VoteWeight)]) -> RunoffResult {
+    let mut remaining: Vec<bool> = (0..option_count).map(|_| true).collect();
+    let mut rounds = Vec::new();
+
+    loop {
+        let mut totals: Vec<VoteWeight> = (0..option_count).map(|_| 0).collect();
+        for (ranking, weight) in ballots {
+            if let Some(option) = preferred(ranking, &remaining) {
+                totals[option as usize] = totals[option as usize].saturating_add(*weight);
+            }
+        }
+        let (round, winner) = close_round(totals, &remaining);
+        match round.eliminated {
+            Some(option) => {
+                remaining[option as usize] = false;
+                rounds.push(round);
+            }
+            None => {
+                rounds.push(round);
+                return RunoffResult { rounds, winner };
+            }
+        }
+    }
+}
+
+// highest ranked option of the ranking still in the race
+pub fn preferred(ranking: &[u8], remaining: &[bool]) -> Option<u8> {
+    ranking.iter().find(|option| remaining.get(**option as usize).cloned().unwrap_or(false)).cloned()
+}
+
+// options still in the race after the rounds
+pub fn remaining(option_count: u8, rounds: &[RunoffRound]) -> Vec<bool> {
+    let mut remaining: Vec<bool> = (0..option_count).map(|_| true).collect();
+    for option in rounds.iter().filter_map(|round| round.eliminated) {
+        remaining[option as usize] = false;
+    }
+    remaining
+}
+
+// Round of the totals and the winner, if any.
+// An option with more than half of the counted weight wins, otherwise the option with the lowest total
+// is eliminated and the round is not the final one. Ties for the lowest total eliminate the option
+// with the highest index, so the order of VoteOptions breaks ties deterministically.
+pub fn close_round(totals: Vec<VoteWeight>, remaining: &[bool]) -> (RunoffRound, Option<u8>) {
+    let running = |option: &u8| remaining.get(*option as usize).cloned().unwrap_or(false);
+    let option_count = totals.len() as u8;
+    // ballots whose options are all eliminated are exhausted
+    let counted = totals.iter().fold(0, |sum: VoteWeight, total| sum.saturating_add(*total));
+    let leader = (0..option_count).filter(running)
+        .max_by(|a, b| totals[*a as usize].cmp(&totals[*b as usize]).then(b.cmp(a)));
+    let winner = leader.filter(|option| counted > 0 && totals[*option as usize] > counted - totals[*option as usize]);
+    // rev() makes the highest index lose ties for the lowest total
+    let last = (0..option_count).rev().filter(running)
+        .min_by_key(|option| totals[*option as usize]);
+    let eliminated = match (winner, last) {
+        (None, Some(option)) if counted > 0 => Some(option),
+        _ => None,
+    };
+    (RunoffRound { totals, eliminated }, winner)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // running totals as the module keeps them, one ballot at a time
+    fn running_totals<S: TallyStrategy>(option_count: u8, ballots: &[(u8, VoteWeight)]) -> Vec<VoteWeight> {
+        let mut totals: Vec<VoteWeight> = (0..option_count).map(|_| 0).collect();
+        for (option, weight) in ballots {
+            totals[*option as usize] += S::weigh(*weight);
+        }
+        totals
+    }
+
+    #[test]
+    fn simple_majority_counts_heads() {
+        let tally = SimpleMajority::tally(running_totals::<SimpleMajority>(2, &[(0, 50), (1, 1), (1, 1)]));
+        assert_eq!(tally.totals, vec![1, 2]);
+        assert_eq!(tally.leader(), Some(1));
+    }
+
+    #[test]
+    fn lock_weighted_sums_weights() {
+        let tally = LockWeighted::tally(running_totals::<LockWeighted>(2, &[(0, 50), (1, 1), (1, 1)]));
+        assert_eq!(tally.totals, vec![50, 2]);
+        assert_eq!(tally.leader(), Some(0));
+    }
+
+    #[test]
+    fn ties_and_empty_tallies() {
+        let tally = SimpleMajority::tally(running_totals::<SimpleMajority>(3, &[(0, 1), (2, 1)]));
+        assert_eq!(tally.totals, vec![1, 0, 1]);
+        assert_eq!(tally.leader(), None);
+        assert_eq!(LockWeighted::tally(vec![0, 0]).leader(), None);
+    }
+
+    #[test]
+    fn instant_runoff_transfers_eliminated_ballots() {
+        let ballots = vec![(vec![0], 1), (vec![0], 1), (vec![1, 0], 1), (vec![1, 0], 1), (vec![2, 1], 1)];
+        let result = instant_runoff(3, &ballots);
+        assert_eq!(result.rounds, vec![
+            RunoffRound { totals: vec![2, 2, 1], eliminated: Some(2) },
+            RunoffRound { totals: vec![2, 3, 0], eliminated: None },
+        ]);
+        assert_eq!(result.winner, Some(1));
+    }
+
+    #[test]
+    fn instant_runoff_weighs_ballots() {
+        // a ballot weighing 3 counts as 3 ballots
+        let result = instant_runoff(3, &[(vec![0], 2), (vec![1, 0], 1), (vec![2, 1], 3)]);
+        assert_eq!(result.rounds, vec![
+            RunoffRound { totals: vec![2, 1, 3], eliminated: Some(1) },
+            RunoffRound { totals: vec![3, 0, 3], eliminated: Some(2) },
+            RunoffRound { totals: vec![3, 0, 0], eliminated: None },
+        ]);
+        assert_eq!(result.winner, Some(0));
+        assert_eq!(remaining(3, &result.rounds[..2]), vec![true, false, false]);
+    }
+
+    #[test]
+    fn instant_runoff_ties_and_exhaustion() {
+        // tie for the lowest total eliminates the higher index
+        let result = instant_runoff(2, &[(vec![0], 1), (vec![1], 1)]);
+        assert_eq!(result.rounds[0].eliminated, Some(1));
+        assert_eq!(result.winner, Some(0));
+
+        // no ballots, no winner
+        let result = instant_runoff(3, &[]);
+        assert_eq!(result.rounds.len(), 1);
+        assert_eq!(result.winner, None);
+    }
+
+    #[test]
+    fn thresholds() {
+        assert!(Threshold::SimpleMajority.approves(2, 3));
+        assert!(!Threshold::SimpleMajority.approves(2, 4));
+        assert!(Threshold::SuperMajority(Perbill::from_percent(66)).approves(2, 3));
+        assert!(!Threshold::SuperMajority(Perbill::from_percent(67)).approves(2, 3));
+        assert!(Threshold::Unanimous.approves(3, 3));
+        assert!(!Threshold::Unanimous.approves(2, 3));
+        assert!(!Threshold::Unanimous.approves(0, 0));
+        assert!(Threshold::SuperMajority(Perbill::from_percent(51)).is_valid());
+        assert!(!Threshold::SuperMajority(Perbill::from_percent(50)).is_valid());
+
+        assert_eq!(outcome(3, 2, Threshold::SimpleMajority, 2, 2), Outcome::QuorumNotMet);
+        assert_eq!(outcome(2, 2, Threshold::SimpleMajority, 2, 2), Outcome::Passed);
+        assert_eq!(outcome(0, 2, Threshold::SimpleMajority, 1, 2), Outcome::Rejected);
+    }
+}
@@ -1,19 +1,29 @@
 #![cfg(test)]
 use super::*;
-use crate::certificate;
+use crate::{certificate, governance};
 use support::{
-    impl_outer_origin, assert_ok, assert_noop, parameter_types,
+    impl_outer_origin, impl_outer_dispatch, assert_ok, assert_noop, parameter_types,
     traits::{Currency}
 };
 use runtime_io::{TestExternalities};
 use primitives::{H256};
+use std::cell::RefCell;
 use sr_primitives::{
-    Perbill, traits::{Hash, IdentityLookup},
+    Perbill, traits::{Hash, IdentityLookup, OnFinalize, OnInitialize},
     testing::{Header}
 };
 
 impl_outer_origin! {
-    pub enum Origin for Test {}
+    pub enum Origin for Test {
+        governance,
+    }
+}
+
+impl_outer_dispatch! {
+    pub enum Call for Test where origin: Origin {
+        balances::Balances,
+        governance::Governance,
+    }
 }
 
 #[derive(Clone, Eq, PartialEq)]
@@ -22,6 +32,26 @@ pub struct Test;
 impl Trait for Test {
     type Event = ();
     type Currency = balances::Module<Test>;
+    type SimpleTally = tally::SimpleMajority;
+    type LockTally = tally::LockWeighted;
+    type MaxConclusionWeight = MaxConclusionWeight;
+    type ProposalOrigin = Origin;
+    type Proposal = Call;
+    type ProposalQuorum = ProposalQuorum;
+    type MaxProposalWeight = MaxProposalWeight;
+    type RevealPeriod = RevealPeriod;
+    type RevealDeposit = RevealDeposit;
+    type Slash = ();
+    type CreationDepositBase = CreationDepositBase;
+    type CreationDepositPerByte = CreationDepositPerByte;
+    type SpamOrigin = system::EnsureRoot<u64>;
+    type CancelOrigin = system::EnsureRoot<u64>;
+    type LockPeriod = LockPeriod;
+    type Convictions = Convictions;
+    type Treasury = ();
+    type RetentionPeriod = RetentionPeriod;
+    type ReapReward = ReapReward;
+    type MaxDelegationDepth = MaxDelegationDepth;
 }
 parameter_types! {
     pub const BlockHashCount: u64 = 250;
@@ -32,6 +62,56 @@ parameter_types! {
     pub const ExistentialDeposit: u64 = 0;
     pub const TransferFee: u64 = 0;
     pub const CreationFee: u64 = 0;
+    pub const RevealPeriod: u64 = 3;
+    pub const RevealDeposit: u64 = 10;
+    pub const LockPeriod: u64 = 10;
+    pub const Convictions: &'static [(u32, u32)] = &[(0, 1), (1, 2), (2, 3), (4, 4)];
+    pub const RetentionPeriod: u64 = 10;
+    pub const ProposalQuorum: u64 = 2;
+    pub const MaxProposalWeight: Weight = 1_000_000;
+    pub const MaxDelegationDepth: u32 = 3;
+}
+
+// creation deposit is free unless a test sets it
+thread_local! {
+    static CREATION_DEPOSIT: RefCell<(u64, u64)> = RefCell::new((0, 0));
+}
+pub struct CreationDepositBase;
+impl Get<u64> for CreationDepositBase {
+    fn get() -> u64 { CREATION_DEPOSIT.with(|deposit| deposit.borrow().0) }
+}
+pub struct CreationDepositPerByte;
+impl Get<u64> for CreationDepositPerByte {
+    fn get() -> u64 { CREATION_DEPOSIT.with(|deposit| deposit.borrow().1) }
+}
+fn set_creation_deposit(base: u64, per_byte: u64) {
+    CREATION_DEPOSIT.with(|deposit| *deposit.borrow_mut() = (base, per_byte));
+}
+
+// two Simple votes per block unless a test sets it. a delegator is MaxDelegationDepth + 1 accounts
+const SIMPLE_VOTE_WEIGHT: Weight = CONCLUDE_BASE_WEIGHT + 3 * CONCLUDE_OPTION_WEIGHT;
+const DELEGATOR_WEIGHT: Weight = 4 * CONCLUDE_ACCOUNT_WEIGHT;
+thread_local! {
+    static MAX_CONCLUSION_WEIGHT: RefCell<Weight> = RefCell::new(2 * SIMPLE_VOTE_WEIGHT + DELEGATOR_WEIGHT);
+}
+pub struct MaxConclusionWeight;
+impl Get<Weight> for MaxConclusionWeight {
+    fn get() -> Weight { MAX_CONCLUSION_WEIGHT.with(|weight| *weight.borrow()) }
+}
+fn set_max_conclusion_weight(weight: Weight) {
+    MAX_CONCLUSION_WEIGHT.with(|cell| *cell.borrow_mut() = weight);
+}
+
+// no reap reward unless a test sets it
+thread_local! {
+    static REAP_REWARD: RefCell<u64> = RefCell::new(0);
+}
+pub struct ReapReward;
+impl Get<u64> for ReapReward {
+    fn get() -> u64 { REAP_REWARD.with(|reward| *reward.borrow()) }
+}
+fn set_reap_reward(reward: u64) {
+    REAP_REWARD.with(|cell| *cell.borrow_mut() = reward);
 }
 
 impl certificate::Trait for Test {
@@ -78,7 +158,7 @@ fn build_ext() -> runtime_io::TestExternalities {
         balances: vec![(1, 100), (2, 100), (10, 100)],
         vesting: vec![],
     }.assimilate_storage(&mut t).unwrap();
-    // GenesisConfig::default().assimilate_storage::<Test>(&mut t).unwrap();
+    GenesisConfig::default().assimilate_storage::<Test>(&mut t).unwrap();
     t.into()
 }
 
@@ -90,6 +170,14 @@ fn run_to_block(n: u64) {
     }
 }
 
+// like run_to_block, finalizing each block on the way
+fn run_to_block_finalizing(n: u64) {
+    while System::block_number() < n {
+        Governance::on_finalize(System::block_number());
+        System::set_block_number(System::block_number() + 1);
+    }
+}
+
 #[test]
 fn it_works() {
     build_ext().execute_with(|| {
@@ -101,10 +189,15 @@ fn it_works() {
 fn should_pass_vote_creation() {
     TestExternalities::default().execute_with(|| {
         // fail no option provided
-        assert_noop!(Governance::create_vote(Origin::signed(10), 0, 10, [00].to_vec(), 0, [].to_vec()), "At least one option should be provided.");
+        assert_noop!(Governance::create_vote(Origin::signed(10), VoteType::Simple, 10, [00].to_vec(), 0, [].to_vec(), VoteSettings::default()), "At least one option should be provided.");
+        // option counts are checked before they are narrowed to u8
+        let options: Vec<Vec<u8>> = (0..256).map(|option| [option as u8].to_vec()).collect();
+        assert_noop!(Governance::create_vote(Origin::signed(10), VoteType::MultiOption, 10, [00].to_vec(), 0, options.clone(), VoteSettings::default()), "Cannot add more than 254 options");
+        let options: Vec<Vec<u8>> = (0..257).map(|option| [option as u8].to_vec()).collect();
+        assert_noop!(Governance::create_vote(Origin::signed(10), VoteType::MultiOption, 10, [00].to_vec(), 0, options, VoteSettings::default()), "Cannot add more than 254 options");
 
         // create a normal vote with account #10.
-        assert_ok!(Governance::create_vote(Origin::signed(10), 0, 10, [00].to_vec(), 0, [[00].to_vec()].to_vec()));
+        assert_ok!(Governance::create_vote(Origin::signed(10), VoteType::Simple, 10, [00].to_vec(), 0, [[00].to_vec()].to_vec(), VoteSettings::default()));
         
         // Vote number shoud be incremented by 1
         assert_eq!(Governance::all_vote_count(), 1);
@@ -114,14 +207,14 @@ fn should_pass_vote_creation() {
         assert_eq!(Governance::creator_of(1), Some(10));
 
         // create a lockvote with account #10
-        assert_ok!(Governance::create_vote(Origin::signed(10), 1, 10, [00].to_vec(), 0, [[00].to_vec()].to_vec()));
+        assert_ok!(Governance::create_vote(Origin::signed(10), VoteType::Lock, 10, [00].to_vec(), 0, [[00].to_vec()].to_vec(), VoteSettings::default()));
         assert_eq!(Governance::all_vote_count(), 2);
 
         let vote = Governance::votes(2);
-        let vote2 = Governance::created_by_and_index((10, 2));
 
-        // vote in VotesByIndex and VoteByCreatorArray should be the same
-        assert_eq!(vote, vote2);
+        // VoteByCreatorArray points at the vote in VotesByIndex
+        assert_eq!(Governance::created_by_and_index((10, 2)), Some(2));
+        assert_eq!(Governance::votes_by_creator(10, 0), vec![Governance::votes(1), Governance::votes(2)]);
 
         // vote expiry block is the sum of the creation block and given blocknumber
         assert_eq!(vote.vote_ends, vote.when + 10);
@@ -129,28 +222,191 @@ fn should_pass_vote_creation() {
     });
 }
 
+#[test]
+fn votes_by_creator() {
+    TestExternalities::default().execute_with(|| {
+        assert_ok!(Governance::create_vote(Origin::signed(11), VoteType::Simple, 10, [00].to_vec(), 0, [[00].to_vec()].to_vec(), VoteSettings::default()));
+        for _ in 0..VOTES_PER_PAGE + 2 {
+            assert_ok!(Governance::create_vote(Origin::signed(10), VoteType::Simple, 10, [00].to_vec(), 0, [[00].to_vec()].to_vec(), VoteSettings::default()));
+        }
+        assert_eq!(Governance::created_by(10), VOTES_PER_PAGE + 2);
+        assert_eq!(Governance::created_by_and_index((10, 1)), Some(2));
+
+        let first_page = Governance::votes_by_creator(10, 0);
+        assert_eq!(first_page.len() as u64, VOTES_PER_PAGE);
+        assert_eq!(first_page[0], Governance::votes(2));
+        let ids: Vec<u64> = Governance::votes_by_creator(10, 1).iter().map(|vote| vote.id).collect();
+        assert_eq!(ids, [22, 23].to_vec());
+        assert_eq!(Governance::votes_by_creator(11, 0), vec![Governance::votes(1)]);
+        assert!(Governance::votes_by_creator(11, 1).is_empty());
+    });
+}
+
+#[test]
+fn migrate_creator_index() {
+    TestExternalities::default().execute_with(|| {
+        for creator in [10, 11, 10].iter() {
+            assert_ok!(Governance::create_vote(Origin::signed(*creator), VoteType::Simple, 10, [00].to_vec(), 0, [[00].to_vec()].to_vec(), VoteSettings::default()));
+        }
+        // older runtimes kept a copy of each vote under (creator, reference_index)
+        for (creator, index) in [(10, 1), (11, 1), (10, 2)].iter() {
+            <VoteByCreatorArray<Test>>::remove((*creator, *index));
+        }
+        for reference_index in 1..4 {
+            let vote = Governance::votes(reference_index);
+            let key = <VoteByCreatorArray<Test>>::hashed_key_for((vote.creator, reference_index));
+            support::storage::unhashed::put(&key, &vote);
+        }
+
+        migration::migrate_creator_index::<Test>();
+        assert_eq!(Governance::created_by_and_index((10, 1)), Some(1));
+        assert_eq!(Governance::created_by_and_index((10, 2)), Some(3));
+        assert_eq!(Governance::created_by_and_index((11, 1)), Some(2));
+        // the copies at (11, 2) and (10, 3) are gone
+        assert_eq!(Governance::created_by_and_index((11, 2)), None);
+        assert_eq!(Governance::created_by_and_index((10, 3)), None);
+    });
+}
+
+#[test]
+fn migrate_v0_storage() {
+    build_ext().execute_with(|| {
+        use support::storage::unhashed;
+        <StorageVersion>::put(0);
+        // a concluded lockvote, and an open and a concluded vote of vote_type 0 with option ballots, of the first testnet runtime
+        for (id, vote_type, concluded) in [(1u64, 1u8, true), (2, 0, false), (3, 0, true)].iter() {
+            let vote = migration::VoteV0 { id: *id, vote_type: *vote_type, approved: H256::default(), creator: 10u64, when: 0u64, vote_ends: 5u64, concluded: *concluded };
+            unhashed::put(&<VotesByIndex<Test>>::hashed_key_for(id), &vote);
+            unhashed::put(&<VoteByCreatorArray<Test>>::hashed_key_for((10, *id)), &vote);
+            <VoteCreator<Test>>::insert(id, 10);
+        }
+        <AllVoteCount>::put(3);
+        <CreatedVoteCount<Test>>::insert(10, 3);
+        <VoteOptions>::insert(2, [[00].to_vec(), [01].to_vec()].to_vec());
+        <VoteOptions>::insert(3, [[00].to_vec(), [01].to_vec()].to_vec());
+        unhashed::put(&<VoteResults<Test>>::hashed_key_for(1), &[50u64, 0].to_vec());
+        unhashed::put(&<VoteResults<Test>>::hashed_key_for(3), &[1u64, 0].to_vec());
+        // each lockvote under a lock named after the vote
+        for (voter, deposit, slot) in [(1u64, 10u64, 0u8), (2, 20, 1)].iter() {
+            <LockBalance<Test>>::insert((1, voter), LockInfo { deposit: *deposit, duration: 5, until: 5 });
+            Balances::set_lock(1u64.to_be_bytes(), voter, *deposit, u64::max_value(), WithdrawReasons::except(WithdrawReason::TransactionPayment));
+            unhashed::put(&migration::legacy_key(b"VotedAccounts", &(1u64, *slot)), &[*voter].to_vec());
+        }
+        // 4 voted for the slot past the last option, 5 cast an Aye ballot besides
+        for (id, option, voters) in [(2u64, 1u8, [3u64].to_vec()), (2, 2, [4].to_vec()), (3, 1, [1, 2].to_vec())].iter() {
+            unhashed::put(&migration::legacy_key(b"AccountsByOption", &(*id, *option)), voters);
+            for voter in voters {
+                unhashed::put(&migration::legacy_key(b"VotedOption", &(*id, *voter)), option);
+            }
+        }
+        for id in [2u64, 3].iter() {
+            unhashed::put(&migration::legacy_key(b"VotedAccounts", &(*id, 0u8)), &[5u64].to_vec());
+        }
+
+        Governance::on_initialize(1);
+        assert_eq!(Governance::storage_version(), migration::STORAGE_VERSION);
+        let vote = Governance::votes(1);
+        assert_eq!((vote.vote_type, vote.starts_at, vote.vote_ends, vote.concluded), (VoteType::Lock, 0, 5, true));
+        assert_eq!((vote.quorum, vote.threshold), (0, Threshold::SimpleMajority));
+        assert_eq!(Governance::votes(2).vote_type, VoteType::MultiOption);
+        assert_eq!(Governance::votes(3).vote_type, VoteType::MultiOption);
+        let result = Governance::result_of(1);
+        assert_eq!(result.tally.totals, [50, 0].to_vec());
+        assert_eq!((result.head_counts, result.ballots, result.turnout), ([1, 1, 0].to_vec(), 2, 2));
+        assert_eq!((result.eligible, result.concluded_at), (None, 6));
+        assert_eq!(Governance::option_head_count((2, 1)), 1);
+        assert_eq!(Governance::created_by_and_index((10, 2)), Some(2));
+        // the stored [aye, nay] of vote 3 gives way to its option totals
+        let result = Governance::result_of(3);
+        assert_eq!(result.tally.totals, [0, 2].to_vec());
+        assert_eq!((result.head_counts, result.ballots), ([0, 2].to_vec(), 2));
+
+        // lockvotes lasting until vote_ends weigh deposit * 1
+        assert_eq!(Governance::ballot_of(1, 2), Some(BallotRecord { ballot: OptionBallot::Binary(Ballot::Nay), weight: 20 }));
+        assert_eq!((Governance::option_total((1, 0)), Governance::option_total((1, 1))), (10, 20));
+        assert_eq!(Governance::ballot_of(2, 3), Some(BallotRecord { ballot: OptionBallot::Single(1), weight: 1 }));
+        assert_eq!((Governance::ballot_of(2, 4), Governance::ballot_of(2, 5), Governance::ballot_of(3, 5)), (None, None, None));
+        assert_eq!(Governance::voter_count(2), 1);
+        assert_eq!(unhashed::get_raw(&migration::legacy_key(b"VotedAccounts", &(1u64, 0u8))), None);
+        assert_eq!(unhashed::get_raw(&migration::legacy_key(b"VotedAccounts", &(2u64, 0u8))), None);
+        for (id, option, voter) in [(2u64, 1u8, 3u64), (2, 2, 4), (3, 1, 1)].iter() {
+            assert_eq!(unhashed::get_raw(&migration::legacy_key(b"AccountsByOption", &(*id, *option))), None);
+            assert_eq!(unhashed::get_raw(&migration::legacy_key(b"VotedOption", &(*id, *voter))), None);
+        }
+
+        // the per-vote locks are replaced by the governance lock, and can be withdrawn
+        assert_eq!(Balances::locks(&2).len(), 1);
+        assert_eq!((Balances::locks(&2)[0].id, Balances::locks(&2)[0].amount), (GOVERNANCE_LOCK, 20));
+        assert_eq!(Governance::locked_votes_of(2), [1].to_vec());
+        run_to_block(6);
+        assert_ok!(Governance::withdraw(Origin::signed(2), 1));
+        assert_eq!(Balances::locks(&2).len(), 0);
+
+        // migrations run once
+        Governance::on_initialize(6);
+        assert_eq!(Governance::option_total((1, 1)), 20);
+    });
+}
+
+#[test]
+fn migrate_v1_storage() {
+    build_ext().execute_with(|| {
+        use support::storage::unhashed;
+        register_ca_and_accounts(3);
+        let options = [[00].to_vec()].to_vec();
+        assert_ok!(Governance::create_vote(Origin::signed(10), VoteType::CommitReveal, 5, [00].to_vec(), 0, options.clone(), VoteSettings::default()));
+        assert_ok!(Governance::create_vote(Origin::signed(10), VoteType::Simple, 5, [00].to_vec(), 0, options, VoteSettings::default()));
+        <StorageVersion>::put(1);
+        // commitments of the version 1 layout, listed in a Vec of committers
+        for committer in [1u64, 2].iter() {
+            assert_ok!(Balances::reserve(committer, 10));
+            <Commitments<Test>>::insert((1u64, committer), (H256::repeat_byte(*committer as u8), 10));
+        }
+        unhashed::put(&migration::legacy_key(b"Committers", &1u64), &[1u64, 2].to_vec());
+        // a concluded vote with its Tally, whose ballot counts for a delegator
+        assert_ok!(Governance::cast_ballot(Origin::signed(1), 2, Ballot::Aye));
+        assert_ok!(Governance::delegate(Origin::signed(3), 1, None));
+        <VotesByIndex<Test>>::mutate(2, |vote| vote.concluded = true);
+        unhashed::put(&<VoteResults<Test>>::hashed_key_for(2), &Tally { totals: [1, 0, 0].to_vec() });
+
+        Governance::on_initialize(1);
+        assert_eq!(Governance::storage_version(), migration::STORAGE_VERSION);
+        // the commitments no longer open, their deposits are returned
+        assert_eq!(unhashed::get_raw(&migration::legacy_key(b"Committers", &1u64)), None);
+        for committer in [1u64, 2].iter() {
+            assert_eq!(Governance::commitment_of((1u64, committer)), None);
+            assert_eq!(Balances::reserved_balance(committer), 0);
+            assert_eq!(Balances::free_balance(committer), 100);
+        }
+        // delegations are not resolved again: the tally is kept, the rest counts the ballots cast directly
+        let result = Governance::result_of(2);
+        assert_eq!(result.tally.totals, [1, 0, 0].to_vec());
+        assert_eq!((result.head_counts, result.ballots, result.turnout), ([1, 0, 0].to_vec(), 1, 1));
+        assert_eq!(result.concluded_at, 6);
+    });
+}
+
 #[test]
 fn cast_ballot() {
     TestExternalities::default().execute_with(|| {
         let ballot = Ballot::Aye;
-        assert_ok!(Governance::create_vote(Origin::signed(10), 0, 10, [00].to_vec(), 0, [[00].to_vec()].to_vec()));
+        assert_ok!(Governance::create_vote(Origin::signed(10), VoteType::Simple, 10, [00].to_vec(), 0, [[00].to_vec()].to_vec(), VoteSettings::default()));
         // should pass cast ballot, check vote_type
         assert_ok!(Governance::cast_ballot(Origin::signed(1), 1, ballot));
         assert_noop!(Governance::cast_ballot(Origin::signed(10), 1, ballot), "You cannot vote your own vote.");
         
-        let aye_accounts = <VotedAccounts<Test>>::get((1, 0));
-        let nay_accounts = <VotedAccounts<Test>>::get((1, 1));
+        assert_eq!(Governance::ballot_of(1, 1), Some(BallotRecord { ballot: OptionBallot::Binary(ballot), weight: 1 }));
         
-        // vote count adds up in respective VotedAccounts array
-        assert_eq!(aye_accounts.len(), 1);
-        assert_eq!(nay_accounts.len(), 0);
+        // vote count adds up in the running total of the ballot
+        assert_eq!(Governance::option_total((1, 0)), 1);
+        assert_eq!(Governance::option_total((1, 1)), 0);
     });
 }
 #[test]
 fn cast_ballot_with_options() {
     TestExternalities::default().execute_with(|| {
         let ballot = Ballot::Aye;
-        assert_ok!(Governance::create_vote(Origin::signed(10), 0, 10, [00].to_vec(), 0, [[00].to_vec(), [01].to_vec(), [02].to_vec()].to_vec()));
+        assert_ok!(Governance::create_vote(Origin::signed(10), VoteType::MultiOption, 10, [00].to_vec(), 0, [[00].to_vec(), [01].to_vec(), [02].to_vec()].to_vec(), VoteSettings::default()));
         
         // cast ballot on the first option
         assert_ok!(Governance::cast_ballot_with_options(Origin::signed(1), 1, 0));
@@ -162,13 +418,29 @@ fn cast_ballot_with_options() {
 
         // can update option. Change 0 => 1
         assert_ok!(Governance::cast_ballot_with_options(Origin::signed(1), 1, 1));
-        assert_eq!(<AccountsByOption<Test>>::get((1, 0)).len() as u8, 0);
-        assert_eq!(<AccountsByOption<Test>>::get((1, 1)).len() as u8, 1);
-        assert_eq!(<VotedOption<Test>>::get((1, 1)), 1);
+        assert_eq!(Governance::option_total((1, 0)), 0);
+        assert_eq!(Governance::option_total((1, 1)), 1);
+        assert_eq!(Governance::ballot_of(1, 1), Some(BallotRecord { ballot: OptionBallot::Single(1), weight: 1 }));
+        assert_eq!(Governance::voter_count(1), 1);
         
     });
 }
 
+#[test]
+fn vote_type_validation() {
+    TestExternalities::default().execute_with(|| {
+        // multi-option vote needs at least two options
+        assert_noop!(Governance::create_vote(Origin::signed(10), VoteType::MultiOption, 10, [00].to_vec(), 0, [[00].to_vec()].to_vec(), VoteSettings::default()), "Multi-option vote needs at least two options.");
+        assert_ok!(Governance::create_vote(Origin::signed(10), VoteType::MultiOption, 10, [00].to_vec(), 0, [[00].to_vec(), [01].to_vec()].to_vec(), VoteSettings::default()));
+        assert_eq!(Governance::votes(1).vote_type, VoteType::MultiOption);
+
+        // ballots are only accepted by the cast function of the vote_type
+        assert_noop!(Governance::cast_ballot(Origin::signed(1), 1, Ballot::Aye), "This vote is not a simple vote. Use the cast function of its vote_type instead!");
+        assert_ok!(Governance::create_vote(Origin::signed(10), VoteType::Simple, 10, [00].to_vec(), 0, [[00].to_vec()].to_vec(), VoteSettings::default()));
+        assert_noop!(Governance::cast_ballot_with_options(Origin::signed(1), 2, 0), "This vote is not a multi-option vote.");
+    });
+}
+
 fn set_free_balance() {
     let total_balance_before = Balances::total_balance(&1);
     assert_eq!(total_balance_before, 100);
@@ -186,11 +458,11 @@ fn account_should_be_registered() {
         let signature = sr_primitives::traits::BlakeTwo256::hash(&[11, 12, 13, 14]);
         let data = [11, 12, 13, 14].to_vec();
         // new ca at index 1
-        assert_ok!(Certificate::register_ca(Origin::signed(1), ca_hash, data));
+        assert_ok!(Certificate::register_ca(Origin::signed(1), ca_hash, data.clone(), data));
 
-        assert_ok!(Governance::create_vote(Origin::signed(10), 0, 10, [00].to_vec(), 0, [[00].to_vec()].to_vec()));
+        assert_ok!(Governance::create_vote(Origin::signed(10), VoteType::Simple, 10, [00].to_vec(), 0, [[00].to_vec()].to_vec(), VoteSettings::default()));
         // requires ca at 1
-        assert_ok!(Governance::create_vote(Origin::signed(10), 0, 10, [00].to_vec(), 1, [[00].to_vec()].to_vec()));
+        assert_ok!(Governance::create_vote(Origin::signed(10), VoteType::Simple, 10, [00].to_vec(), 1, [[00].to_vec()].to_vec(), VoteSettings::default()));
 
         // should suceed casting ballot which doesnt require account 1 to be approved
         assert_ok!(Governance::cast_ballot(Origin::signed(1), 1, ballot));
@@ -211,8 +483,8 @@ fn cast_lockvote() {
         set_free_balance();
 
         let ballot = Ballot::Aye;
-        assert_ok!(Governance::create_vote(Origin::signed(10), 0, 5, [00].to_vec(), 0, [[00].to_vec()].to_vec()));
-        assert_ok!(Governance::create_vote(Origin::signed(10), 1, 5, [00].to_vec(), 0, [[00].to_vec()].to_vec()));
+        assert_ok!(Governance::create_vote(Origin::signed(10), VoteType::Simple, 5, [00].to_vec(), 0, [[00].to_vec()].to_vec(), VoteSettings::default()));
+        assert_ok!(Governance::create_vote(Origin::signed(10), VoteType::Lock, 5, [00].to_vec(), 0, [[00].to_vec()].to_vec(), VoteSettings::default()));
 
         let vote = Governance::votes(1);
         // vote should be active
@@ -227,7 +499,7 @@ fn cast_lockvote() {
         // should succeed casting lockvote
         assert_ok!(Governance::cast_lockvote(Origin::signed(1), 2, ballot, 1, 10));
 
-        // [BalanceLock { id: [0, 0, 0, 0, 0, 0, 0, 2], amount: 1, until: `18446744073709551615`, reasons: WithdrawReasons { mask: 14 } }]
+        // [BalanceLock { id: *b"mgovernc", amount: 1, until: `18446744073709551615`, reasons: WithdrawReasons { mask: 14 } }]
         let locked_balance = Balances::locks(&1);
         assert_eq!(1, locked_balance[0].amount);
         assert_eq!(u64::max_value().saturated_into::<u64>(), Balances::locks(&1)[0].until);
@@ -247,6 +519,7 @@ fn cast_lockvote() {
         assert_eq!(System::block_number(), 15);
         // This vote has already been expired.
         assert_noop!(Governance::cast_lockvote(Origin::signed(2), 2, ballot, 1, 10), "This vote has already been expired.");
+        assert_noop!(Governance::cast_lockvote(Origin::signed(2), 2, ballot, 1, u64::max_value()), "Overflow when setting the lock duration.");
     });
 }
 
@@ -255,7 +528,7 @@ fn withdraw() {
     build_ext().execute_with(|| {
         set_free_balance();
         // create vote. vote.vote_ends = 1 + 5 = 6
-        assert_ok!(Governance::create_vote(Origin::signed(10), 1, 5, [00].to_vec(), 0, [[00].to_vec()].to_vec()));
+        assert_ok!(Governance::create_vote(Origin::signed(10), VoteType::Lock, 5, [00].to_vec(), 0, [[00].to_vec()].to_vec(), VoteSettings::default()));
         // cast_lock vote
         assert_ok!(Governance::cast_lockvote(Origin::signed(1), 1, Ballot::Aye, 1, 10));
 
@@ -266,7 +539,7 @@ fn withdraw() {
         assert_noop!(Governance::withdraw(Origin::signed(1), 1), "You have to wait at least until the vote concludes!");
 
         // cannot conclude before vote is expired
-        assert_noop!(Governance::conclude_vote(Origin::signed(1),1), "This vote hasn\'t been expired yet.");
+        assert_noop!(Governance::conclude_vote(Origin::signed(1), 1, 10), "This vote hasn\'t been expired yet.");
 
         // proceed #1 -> #7. vote.vote_ends is 6 because #1 + duration.
         run_to_block(7);
@@ -276,7 +549,7 @@ fn withdraw() {
         assert_noop!(Governance::withdraw(Origin::signed(1), 1), "You have to wait at least until the vote concludes!");
 
         // conclude the vote
-        assert_ok!(Governance::conclude_vote(Origin::signed(1), 1));
+        assert_ok!(Governance::conclude_vote(Origin::signed(1), 1, 10));
 
         // withdraw after conclude. Still need to wait until the lock period is over
         assert_noop!(Governance::withdraw(Origin::signed(1), 1), "You need to wait until the lock period is over!");
@@ -296,17 +569,907 @@ fn withdraw() {
     });
 }
 
+#[test]
+fn withdraw_early() {
+    build_ext().execute_with(|| {
+        set_free_balance();
+        let options = [[00].to_vec()].to_vec();
+        let early_unlock = EarlyUnlock { penalty: Perbill::from_percent(10), destination: PenaltyDestination::Burn };
+        assert_noop!(Governance::create_vote(Origin::signed(10), VoteType::Simple, 5, [00].to_vec(), 0, options.clone(), VoteSettings { early_unlock: Some(early_unlock), ..Default::default() }), "Early withdrawal only applies to lockvotes.");
+        assert_ok!(Governance::create_vote(Origin::signed(10), VoteType::Lock, 5, [00].to_vec(), 0, options.clone(), VoteSettings { early_unlock: Some(early_unlock), ..Default::default() }));
+        assert_ok!(Governance::create_vote(Origin::signed(10), VoteType::Lock, 5, [00].to_vec(), 0, options, VoteSettings::default()));
+        assert_ok!(Governance::cast_lockvote(Origin::signed(1), 1, Ballot::Aye, 50, 30));
+        assert_ok!(Governance::cast_lockvote(Origin::signed(2), 1, Ballot::Nay, 50, 10));
+        assert_ok!(Governance::cast_lockvote(Origin::signed(1), 2, Ballot::Aye, 20, 30));
+        let issuance = Balances::total_issuance();
+
+        assert_noop!(Governance::withdraw_early(Origin::signed(1), 1), "You have to wait at least until the vote concludes!");
+        run_to_block(7);
+        assert_ok!(Governance::conclude_vote(Origin::signed(1), 1, 10));
+        assert_ok!(Governance::conclude_vote(Origin::signed(1), 2, 10));
+        assert_noop!(Governance::withdraw_early(Origin::signed(1), 2), "This vote doesn't allow early withdrawal.");
+
+        // 10% of the deposit is burned, the rest is released
+        assert_ok!(Governance::withdraw_early(Origin::signed(1), 1));
+        assert_eq!(Balances::free_balance(&1), 95);
+        assert_eq!(Balances::total_issuance(), issuance - 5);
+        assert_eq!(<LockBalance<Test>>::exists((1, 1)), false);
+        // the lock on vote 2 is still held
+        assert_eq!(Balances::locks(&1)[0].amount, 20);
+
+        // after the lock period, withdraw is free
+        run_to_block(11);
+        assert_noop!(Governance::withdraw_early(Origin::signed(2), 1), "The lock period is over. Use withdraw instead.");
+        assert_ok!(Governance::withdraw(Origin::signed(2), 1));
+        assert_eq!(Balances::free_balance(&2), 100);
+    });
+}
+
+#[test]
+fn withdraw_all() {
+    build_ext().execute_with(|| {
+        set_free_balance();
+        let options = [[00].to_vec()].to_vec();
+        assert_ok!(Governance::create_vote(Origin::signed(10), VoteType::Lock, 5, [00].to_vec(), 0, options.clone(), VoteSettings::default()));
+        assert_ok!(Governance::create_vote(Origin::signed(10), VoteType::Lock, 5, [00].to_vec(), 0, options, VoteSettings::default()));
+        assert_ok!(Governance::cast_lockvote(Origin::signed(1), 1, Ballot::Aye, 30, 10));
+        assert_ok!(Governance::cast_lockvote(Origin::signed(1), 2, Ballot::Nay, 10, 40));
+
+        // one lock for both votes, sized to the larger deposit
+        assert_eq!(Balances::locks(&1).len(), 1);
+        assert_eq!(Balances::locks(&1)[0].amount, 30);
+        assert_eq!(Governance::locked_votes_of(1), [1, 2].to_vec());
+
+        run_to_block(7);
+        assert_noop!(Governance::withdraw_all(Origin::signed(1)), "No lock can be withdrawn yet.");
+        assert_ok!(Governance::conclude_vote(Origin::signed(1), 1, 10));
+        assert_ok!(Governance::conclude_vote(Origin::signed(1), 2, 10));
+
+        // the lock on vote 1 is over, the lock shrinks to the deposit on vote 2
+        run_to_block(11);
+        assert_ok!(Governance::withdraw_all(Origin::signed(1)));
+        assert_eq!(Balances::locks(&1)[0].amount, 10);
+        assert_eq!(Governance::locked_votes_of(1), [2].to_vec());
+
+        run_to_block(41);
+        assert_ok!(Governance::withdraw_all(Origin::signed(1)));
+        assert_eq!(Balances::locks(&1).len(), 0);
+        assert_eq!(Governance::locked_votes_of(1).len(), 0);
+    });
+}
+
 #[test]
 fn conclude() {
     TestExternalities::default().execute_with(|| {
-        assert_ok!(Governance::create_vote(Origin::signed(10), 0, 5, [00].to_vec(), 0, [[00].to_vec()].to_vec()));
+        assert_ok!(Governance::create_vote(Origin::signed(10), VoteType::Simple, 5, [00].to_vec(), 0, [[00].to_vec()].to_vec(), VoteSettings::default()));
 
         // proceed #1 -> #15
         run_to_block(15);
 
         assert_eq!(System::block_number(), 15);
-        assert_ok!(Governance::conclude_vote(Origin::signed(1), 1));
-        assert_noop!(Governance::conclude_vote(Origin::signed(1), 1), "This vote has already concluded.");
+        assert_ok!(Governance::conclude_vote(Origin::signed(1), 1, 10));
+        assert_noop!(Governance::conclude_vote(Origin::signed(1), 1, 10), "This vote has already concluded.");
+    });
+}
+
+#[test]
+fn conclude_multi_option() {
+    TestExternalities::default().execute_with(|| {
+        assert_ok!(Governance::create_vote(Origin::signed(10), VoteType::MultiOption, 5, [00].to_vec(), 0, [[00].to_vec(), [01].to_vec(), [02].to_vec()].to_vec(), VoteSettings::default()));
+        assert_ok!(Governance::cast_ballot_with_options(Origin::signed(1), 1, 2));
+        assert_ok!(Governance::cast_ballot_with_options(Origin::signed(2), 1, 2));
+        assert_ok!(Governance::cast_ballot_with_options(Origin::signed(3), 1, 0));
+        // changed ballots are counted once, for the latest option
+        assert_ok!(Governance::cast_ballot_with_options(Origin::signed(4), 1, 0));
+        assert_ok!(Governance::cast_ballot_with_options(Origin::signed(4), 1, 2));
+
+        run_to_block(15);
+        assert_ok!(Governance::conclude_vote(Origin::signed(1), 1, 10));
+
+        // one count for each of VoteOptions
+        assert_eq!(Governance::result_of(1).tally, Tally { totals: [1, 0, 3].to_vec() });
+    });
+}
+
+#[test]
+fn conclude_lockvote() {
+    build_ext().execute_with(|| {
+        set_free_balance();
+        assert_ok!(Governance::create_vote(Origin::signed(10), VoteType::Lock, 5, [00].to_vec(), 0, [[00].to_vec()].to_vec(), VoteSettings::default()));
+        // vote_ends is 5. locks can last 4 LockPeriods of 10 blocks past it
+        assert_noop!(Governance::cast_lockvote(Origin::signed(2), 1, Ballot::Nay, 3, 46), "Lock duration exceeds the longest conviction period.");
+        // 0 periods past vote_ends: x1
+        assert_ok!(Governance::cast_lockvote(Origin::signed(1), 1, Ballot::Aye, 10, 10));
+        // 3 full periods past vote_ends: x3 of the entry for 2 periods
+        assert_ok!(Governance::cast_lockvote(Origin::signed(2), 1, Ballot::Nay, 3, 40));
+
+        run_to_block(7);
+        assert_ok!(Governance::conclude_vote(Origin::signed(1), 1, 10));
+
+        // power is deposit * multiplier, nay is summed from nay accounts
+        let result = Governance::result_of(1).tally;
+        assert_eq!(result.totals, [10, 9, 0].to_vec());
+        assert_eq!(result.leader(), Some(0));
+    });
+}
+
+#[test]
+fn abstain() {
+    build_ext().execute_with(|| {
+        set_free_balance();
+        assert_ok!(Governance::create_vote(Origin::signed(10), VoteType::Simple, 5, [00].to_vec(), 0, [[00].to_vec()].to_vec(), VoteSettings { quorum: 3, ..Default::default() }));
+        assert_ok!(Governance::create_vote(Origin::signed(10), VoteType::Lock, 5, [00].to_vec(), 0, [[00].to_vec()].to_vec(), VoteSettings::default()));
+        assert_ok!(Governance::cast_ballot(Origin::signed(1), 1, Ballot::Aye));
+        assert_ok!(Governance::cast_ballot(Origin::signed(2), 1, Ballot::Nay));
+        // changes from nay to abstain
+        assert_ok!(Governance::cast_ballot(Origin::signed(2), 1, Ballot::Abstain));
+        assert_noop!(Governance::cast_ballot(Origin::signed(2), 1, Ballot::Abstain), "You have already abstained.");
+        assert_ok!(Governance::cast_ballot(Origin::signed(3), 1, Ballot::Abstain));
+        assert_eq!(Governance::option_total((1, 1)), 0);
+        assert_eq!(Governance::option_total((1, 2)), 2);
+        assert_eq!(Governance::voter_count(1), 3);
+
+        assert_ok!(Governance::cast_lockvote(Origin::signed(1), 2, Ballot::Nay, 10, 10));
+        assert_ok!(Governance::cast_lockvote(Origin::signed(2), 2, Ballot::Abstain, 50, 10));
+
+        run_to_block(7);
+        assert_ok!(Governance::conclude_vote(Origin::signed(1), 1, 10));
+        assert_ok!(Governance::conclude_vote(Origin::signed(1), 2, 10));
+        // abstentions meet the quorum of 3 without counting against aye
+        assert_eq!(Governance::result_of(1).tally.totals, [1, 0, 2].to_vec());
+        assert_eq!(Governance::outcome_of(1), Some(Outcome::Passed));
+        assert_eq!(Governance::result_of(2).tally.totals, [0, 10, 50].to_vec());
+        assert_eq!(Governance::outcome_of(2), Some(Outcome::Rejected));
+    });
+}
+
+#[test]
+fn extend_lockvote() {
+    build_ext().execute_with(|| {
+        set_free_balance();
+        assert_ok!(Governance::create_vote(Origin::signed(10), VoteType::Lock, 5, [00].to_vec(), 0, [[00].to_vec()].to_vec(), VoteSettings::default()));
+        assert_ok!(Governance::cast_lockvote(Origin::signed(1), 1, Ballot::Aye, 10, 10));
+        assert_noop!(Governance::cast_lockvote(Origin::signed(1), 1, Ballot::Aye, 10, 10), "You cannot lockvote twice. Use extend_lockvote instead.");
+        assert_noop!(Governance::extend_lockvote(Origin::signed(2), 1, 10, 0), "You have no lockvote to extend.");
+        assert_noop!(Governance::extend_lockvote(Origin::signed(1), 1, 0, 0), "Nothing to extend.");
+        assert_noop!(Governance::extend_lockvote(Origin::signed(1), 1, 90, 0), "You cannot lock more than your free balance!");
+        assert_noop!(Governance::extend_lockvote(Origin::signed(1), 1, 0, 40), "Lock duration exceeds the longest conviction period.");
+
+        // until 30 is 2 periods past vote_ends: x3
+        assert_ok!(Governance::extend_lockvote(Origin::signed(1), 1, 20, 20));
+        let lock_vote = <LockBalance<Test>>::get((1, 1));
+        assert_eq!((lock_vote.deposit, lock_vote.duration, lock_vote.until), (30, 30, 30));
+        assert_eq!(Balances::locks(&1)[0].amount, 30);
+        // the running total follows the new weight
+        assert_eq!(Governance::ballot_of(1, 1).map(|record| record.weight), Some(90));
+        assert_eq!(Governance::option_total((1, 0)), 90);
+
+        run_to_block(5);
+        assert_noop!(Governance::extend_lockvote(Origin::signed(1), 1, 10, 0), "This vote has already been expired.");
+        run_to_block(7);
+        assert_ok!(Governance::conclude_vote(Origin::signed(1), 1, 10));
+        assert_eq!(Governance::result_of(1).tally.totals, [90, 0, 0].to_vec());
+    });
+}
+
+// registers a CA at index 1 and certifies accounts 1 to n for it
+fn register_ca_and_accounts(n: u64) {
+    let ca_hash = sr_primitives::traits::BlakeTwo256::hash(&[111, 112, 113, 114]);
+    let signature = sr_primitives::traits::BlakeTwo256::hash(&[11, 12, 13, 14]);
+    let data = [11, 12, 13, 14].to_vec();
+    assert_ok!(Certificate::register_ca(Origin::signed(1), ca_hash, data.clone(), data));
+    for account in 1..n + 1 {
+        let cert = sr_primitives::traits::BlakeTwo256::hash(&[221, 222, 223, account as u8]);
+        assert_ok!(Certificate::register_account(Origin::signed(account), ca_hash, cert, signature));
+    }
+}
+
+#[test]
+fn cast_quadratic_vote() {
+    build_ext().execute_with(|| {
+        set_free_balance();
+        register_ca_and_accounts(2);
+        let options = [[00].to_vec(), [01].to_vec()].to_vec();
+
+        // quadratic vote needs a certificate
+        assert_noop!(Governance::create_vote(Origin::signed(10), VoteType::Quadratic(CreditPolicy::Lock), 5, [00].to_vec(), 0, options.clone(), VoteSettings::default()), "Quadratic vote requires a certificate to keep 1 person 1 ballot.");
+        // credit locks end with the vote, there is nothing to withdraw early from
+        let early_unlock = EarlyUnlock { penalty: Perbill::from_percent(10), destination: PenaltyDestination::Burn };
+        assert_noop!(Governance::create_vote(Origin::signed(10), VoteType::Quadratic(CreditPolicy::Lock), 5, [00].to_vec(), 1, options.clone(), VoteSettings { early_unlock: Some(early_unlock), ..Default::default() }), "Early withdrawal only applies to lockvotes.");
+        assert_ok!(Governance::create_vote(Origin::signed(10), VoteType::Quadratic(CreditPolicy::Lock), 5, [00].to_vec(), 1, options, VoteSettings::default()));
+
+        // only certified accounts can spend credits, once
+        assert_noop!(Governance::cast_quadratic_vote(Origin::signed(10), 1, 0, 16), "You cannot vote your own vote.");
+        assert_noop!(Governance::cast_quadratic_vote(Origin::signed(3), 1, 0, 16), "Your account is not registered for this CAHash.");
+        assert_ok!(Governance::cast_quadratic_vote(Origin::signed(1), 1, 0, 16));
+        assert_noop!(Governance::cast_quadratic_vote(Origin::signed(1), 1, 1, 16), "You cannot spend credits twice.");
+        assert_ok!(Governance::cast_quadratic_vote(Origin::signed(2), 1, 1, 25));
+
+        // locked credits
+        assert_eq!(Balances::locks(&1)[0].amount, 16);
+
+        run_to_block(7);
+        assert_ok!(Governance::conclude_vote(Origin::signed(1), 1, 10));
+
+        // weight is the square root of spent credits
+        assert_eq!(Governance::result_of(1).tally.totals, [4, 5].to_vec());
+
+        // locked credits can be withdrawn after the vote
+        assert_ok!(Governance::withdraw(Origin::signed(1), 1));
+        assert_eq!(Balances::locks(&1).len(), 0);
+    });
+}
+
+#[test]
+fn quadratic_vote_burns_credits() {
+    build_ext().execute_with(|| {
+        set_free_balance();
+        register_ca_and_accounts(2);
+        let issuance = Balances::total_issuance();
+        assert_ok!(Governance::create_vote(Origin::signed(10), VoteType::Quadratic(CreditPolicy::Burn), 5, [00].to_vec(), 1, [[00].to_vec(), [01].to_vec()].to_vec(), VoteSettings::default()));
+        assert_ok!(Governance::cast_quadratic_vote(Origin::signed(1), 1, 1, 9));
+
+        // burned credits leave the balance and total issuance
+        assert_eq!(Balances::free_balance(&1), 91);
+        assert_eq!(Balances::total_issuance(), issuance - 9);
+        assert_eq!(Balances::locks(&1).len(), 0);
+
+        run_to_block(7);
+        assert_ok!(Governance::conclude_vote(Origin::signed(1), 1, 10));
+        assert_eq!(Governance::result_of(1).tally.totals, [0, 3].to_vec());
+    });
+}
+
+#[test]
+fn cancel_quadratic_vote_forfeits_burned_credits() {
+    build_ext().execute_with(|| {
+        set_free_balance();
+        register_ca_and_accounts(2);
+        let issuance = Balances::total_issuance();
+        assert_ok!(Governance::create_vote(Origin::signed(10), VoteType::Quadratic(CreditPolicy::Burn), 5, [00].to_vec(), 1, [[00].to_vec(), [01].to_vec()].to_vec(), VoteSettings::default()));
+        assert_ok!(Governance::cast_quadratic_vote(Origin::signed(1), 1, 1, 9));
+        assert_noop!(Governance::cancel_vote(Origin::signed(10), 1, 10), "Ballots have already been cast.");
+
+        // root can still cancel. the credits stay burned
+        assert_ok!(Governance::cancel_vote(Origin::ROOT, 1, 10));
+        assert_eq!(Governance::outcome_of(1), Some(Outcome::Cancelled));
+        assert_eq!(Balances::free_balance(&1), 91);
+        assert_eq!(Balances::total_issuance(), issuance - 9);
+        assert_noop!(Governance::cast_quadratic_vote(Origin::signed(2), 1, 0, 4), "This vote has been cancelled.");
+    });
+}
+
+#[test]
+fn ranked_choice() {
+    TestExternalities::default().execute_with(|| {
+        let options = [[00].to_vec(), [01].to_vec(), [02].to_vec()].to_vec();
+        assert_ok!(Governance::create_vote(Origin::signed(10), VoteType::RankedChoice, 5, [00].to_vec(), 0, options, VoteSettings::default()));
+
+        assert_noop!(Governance::cast_ranked_ballot(Origin::signed(1), 1, [].to_vec()), "Ranking should contain at least one option.");
+        assert_noop!(Governance::cast_ranked_ballot(Origin::signed(1), 1, [0, 3].to_vec()), "Provided option out of range.");
+        assert_noop!(Governance::cast_ranked_ballot(Origin::signed(1), 1, [0, 1, 0].to_vec()), "An option can be ranked only once.");
+        assert_noop!(Governance::cast_ranked_ballot(Origin::signed(1), 1, [0, 1, 2, 0].to_vec()), "Ranking cannot contain more options than the vote.");
+
+        // casting again replaces the ranking
+        assert_ok!(Governance::cast_ranked_ballot(Origin::signed(1), 1, [2, 1].to_vec()));
+        assert_ok!(Governance::cast_ranked_ballot(Origin::signed(1), 1, [0].to_vec()));
+        assert_ok!(Governance::cast_ranked_ballot(Origin::signed(2), 1, [0].to_vec()));
+        assert_ok!(Governance::cast_ranked_ballot(Origin::signed(3), 1, [1, 0].to_vec()));
+        assert_ok!(Governance::cast_ranked_ballot(Origin::signed(4), 1, [1, 0].to_vec()));
+        assert_ok!(Governance::cast_ranked_ballot(Origin::signed(5), 1, [2, 1].to_vec()));
+        assert_eq!(Governance::voter_count(1), 5);
+        assert_eq!(Governance::ballot_of(1, 1).map(|record| record.ballot), Some(OptionBallot::Ranked([0].to_vec())));
+
+        // a round visits each ballot for each option: 3 ballots fit in 10 accounts
+        run_to_block(7);
+        assert_ok!(Governance::conclude_vote(Origin::signed(1), 1, 10));
+        assert_eq!(Governance::votes(1).concluded, false);
+        assert_eq!(Governance::count_progress_of(1).map(|counting| (counting.next, counting.totals)), Some((3, [2, 1, 0].to_vec())));
+        assert_ok!(Governance::conclude_vote(Origin::signed(1), 1, 30));
+
+        // option 2 is eliminated, its ballot moves to option 1
+        let runoff = Governance::runoff_results(1);
+        assert_eq!(runoff.rounds.len(), 2);
+        assert_eq!(runoff.rounds[0].totals, [2, 2, 1].to_vec());
+        assert_eq!(runoff.rounds[0].eliminated, Some(2));
+        assert_eq!(runoff.winner, Some(1));
+        assert_eq!(Governance::result_of(1).tally.totals, [2, 3, 0].to_vec());
+        // head counts are first preferences
+        assert_eq!(Governance::result_of(1).head_counts, [2, 2, 1].to_vec());
+    });
+}
+
+#[test]
+fn approval_vote() {
+    TestExternalities::default().execute_with(|| {
+        let options = [[00].to_vec(), [01].to_vec(), [02].to_vec()].to_vec();
+        assert_ok!(Governance::create_vote(Origin::signed(10), VoteType::Approval, 5, [00].to_vec(), 0, options, VoteSettings::default()));
+
+        assert_noop!(Governance::cast_approval_ballot(Origin::signed(1), 1, [].to_vec()), "Approve at least one option.");
+        assert_noop!(Governance::cast_approval_ballot(Origin::signed(1), 1, [0, 0].to_vec()), "An option can be approved only once.");
+        assert_noop!(Governance::cast_approval_ballot(Origin::signed(1), 1, [3].to_vec()), "Provided option out of range.");
+
+        assert_ok!(Governance::cast_approval_ballot(Origin::signed(1), 1, [0, 2].to_vec()));
+        assert_ok!(Governance::cast_approval_ballot(Origin::signed(2), 1, [2].to_vec()));
+        // casting again replaces the approvals
+        assert_ok!(Governance::cast_approval_ballot(Origin::signed(3), 1, [0].to_vec()));
+        assert_ok!(Governance::cast_approval_ballot(Origin::signed(3), 1, [1, 2].to_vec()));
+
+        run_to_block(7);
+        assert_ok!(Governance::conclude_vote(Origin::signed(1), 1, 10));
+        assert_eq!(Governance::result_of(1).tally.totals, [1, 1, 3].to_vec());
+    });
+}
+
+#[test]
+fn score_vote() {
+    TestExternalities::default().execute_with(|| {
+        let options = [[00].to_vec(), [01].to_vec(), [02].to_vec()].to_vec();
+        assert_noop!(Governance::create_vote(Origin::signed(10), VoteType::Score(0), 5, [00].to_vec(), 0, options.clone(), VoteSettings::default()), "Maximum score should be at least 1.");
+        assert_ok!(Governance::create_vote(Origin::signed(10), VoteType::Score(5), 5, [00].to_vec(), 0, options, VoteSettings::default()));
+
+        assert_noop!(Governance::cast_score_ballot(Origin::signed(1), 1, [1, 2].to_vec()), "Provide one score for each option.");
+        assert_noop!(Governance::cast_score_ballot(Origin::signed(1), 1, [1, 2, 6].to_vec()), "Score is above the maximum of this vote.");
+
+        assert_ok!(Governance::cast_score_ballot(Origin::signed(1), 1, [5, 0, 3].to_vec()));
+        assert_ok!(Governance::cast_score_ballot(Origin::signed(2), 1, [1, 4, 3].to_vec()));
+
+        run_to_block(7);
+        assert_ok!(Governance::conclude_vote(Origin::signed(1), 1, 10));
+        assert_eq!(Governance::result_of(1).tally.totals, [6, 4, 6].to_vec());
     });
 }
 
+#[test]
+fn vote_result() {
+    TestExternalities::default().execute_with(|| {
+        register_ca_and_accounts(3);
+        let options = [[00].to_vec(), [01].to_vec(), [02].to_vec()].to_vec();
+        assert_ok!(Governance::create_vote(Origin::signed(10), VoteType::Score(5), 5, [00].to_vec(), 1, options, VoteSettings::default()));
+
+        // options scored 0 count no voter. casting again moves the counts
+        assert_ok!(Governance::cast_score_ballot(Origin::signed(1), 1, [5, 0, 3].to_vec()));
+        assert_ok!(Governance::cast_score_ballot(Origin::signed(2), 1, [1, 4, 0].to_vec()));
+        assert_eq!(Governance::option_head_count((1, 0)), 2);
+        assert_ok!(Governance::cast_score_ballot(Origin::signed(2), 1, [0, 4, 0].to_vec()));
+        assert_eq!(Governance::option_head_count((1, 0)), 1);
+
+        run_to_block(7);
+        assert_ok!(Governance::conclude_vote(Origin::signed(1), 1, 10));
+        let result = Governance::result_of(1);
+        assert_eq!(result.tally.totals, [5, 4, 3].to_vec());
+        assert_eq!(result.head_counts, [1, 1, 1].to_vec());
+        assert_eq!((result.ballots, result.turnout), (2, 2));
+        // 3 accounts hold the certificate of the vote
+        assert_eq!((result.eligible, result.concluded_at), (Some(3), 7));
+    });
+}
+
+#[test]
+fn quorum_and_threshold() {
+    TestExternalities::default().execute_with(|| {
+        let options = [[00].to_vec()].to_vec();
+        let two_thirds = Threshold::SuperMajority(Perbill::from_percent(66));
+        // a share of half or less would let the opposition pass the vote
+        for percent in [0, 10, 50].iter() {
+            let threshold = Threshold::SuperMajority(Perbill::from_percent(*percent));
+            assert_noop!(Governance::create_vote(Origin::signed(10), VoteType::Simple, 5, [00].to_vec(), 0, options.clone(), VoteSettings { quorum: 3, threshold, ..Default::default() }), "Super majority should be more than half.");
+        }
+        assert_ok!(Governance::create_vote(Origin::signed(10), VoteType::Simple, 5, [00].to_vec(), 0, options.clone(), VoteSettings { quorum: 3, threshold: two_thirds, ..Default::default() }));
+        assert_ok!(Governance::create_vote(Origin::signed(10), VoteType::Simple, 5, [00].to_vec(), 0, options.clone(), VoteSettings { quorum: 3, threshold: Threshold::Unanimous, ..Default::default() }));
+        assert_ok!(Governance::create_vote(Origin::signed(10), VoteType::Simple, 5, [00].to_vec(), 0, options.clone(), VoteSettings { quorum: 4, ..Default::default() }));
+        for reference_index in 1..4 {
+            assert_ok!(Governance::cast_ballot(Origin::signed(1), reference_index, Ballot::Aye));
+            assert_ok!(Governance::cast_ballot(Origin::signed(2), reference_index, Ballot::Aye));
+            assert_ok!(Governance::cast_ballot(Origin::signed(3), reference_index, Ballot::Nay));
+        }
+        assert_eq!(Governance::outcome_of(1), None);
+
+        run_to_block(7);
+        for reference_index in 1..4 {
+            assert_ok!(Governance::conclude_vote(Origin::signed(1), reference_index, 10));
+        }
+        assert_eq!(Governance::outcome_of(1), Some(Outcome::Passed));
+        assert_eq!(Governance::outcome_of(2), Some(Outcome::Rejected));
+        assert_eq!(Governance::outcome_of(3), Some(Outcome::QuorumNotMet));
+    });
+}
+
+#[test]
+fn conclude_in_on_finalize() {
+    TestExternalities::default().execute_with(|| {
+        // vote_ends is 5, concluded at the end of block 6
+        for _ in 0..4 {
+            assert_ok!(Governance::create_vote(Origin::signed(10), VoteType::Simple, 5, [00].to_vec(), 0, [[00].to_vec()].to_vec(), VoteSettings::default()));
+        }
+        assert_ok!(Governance::cast_ballot(Origin::signed(1), 1, Ballot::Aye));
+        assert_eq!(Governance::expiring_at(6), [1, 2, 3, 4].to_vec());
+
+        // concluded manually before its turn
+        run_to_block_finalizing(6);
+        assert_ok!(Governance::conclude_vote(Origin::signed(1), 4, 10));
+
+        // 2 votes use the weight of the block, the rest rolls to block 7
+        run_to_block_finalizing(7);
+        assert_eq!(Governance::votes(1).concluded, true);
+        assert_eq!(Governance::votes(2).concluded, true);
+        assert_eq!(Governance::votes(3).concluded, false);
+        assert_eq!(Governance::outcome_of(1), Some(Outcome::Passed));
+        assert_eq!(Governance::expiring_at(7), [3, 4].to_vec());
+
+        run_to_block_finalizing(8);
+        assert_eq!(Governance::votes(3).concluded, true);
+        assert_eq!(Governance::expiring_at(7).len(), 0);
+    });
+}
+
+#[test]
+fn conclude_heavy_vote() {
+    TestExternalities::default().execute_with(|| {
+        register_ca_and_accounts(3);
+        set_max_conclusion_weight(SIMPLE_VOTE_WEIGHT + DELEGATOR_WEIGHT - 1);
+        assert_ok!(Governance::create_vote(Origin::signed(10), VoteType::Simple, 5, [00].to_vec(), 0, [[00].to_vec()].to_vec(), VoteSettings::default()));
+        assert_ok!(Governance::cast_ballot(Origin::signed(1), 1, Ballot::Aye));
+        assert_ok!(Governance::delegate(Origin::signed(2), 1, None));
+        assert_ok!(Governance::delegate(Origin::signed(3), 1, None));
+
+        // a delegator weighs more than any block gives on_finalize, so the vote does not roll
+        run_to_block_finalizing(7);
+        assert_eq!(Governance::votes(1).concluded, false);
+        assert_eq!(Governance::expiring_at(7).len(), 0);
+
+        // conclude_vote counts max_accounts accounts a call, and delegations wait for the rest
+        assert_ok!(Governance::conclude_vote(Origin::signed(1), 1, 7));
+        assert_eq!(Governance::votes(1).concluded, false);
+        assert_eq!(Governance::count_progress_of(1).map(|counting| counting.turnout), Some(2));
+        assert_eq!(Governance::counting_votes(), 1);
+        assert_noop!(Governance::undelegate(Origin::signed(2), None), "Delegations cannot change while a vote counts them.");
+        assert_ok!(Governance::conclude_vote(Origin::signed(1), 1, 4));
+        assert_eq!(Governance::outcome_of(1), Some(Outcome::Passed));
+        assert_eq!(Governance::result_of(1).turnout, 3);
+        assert_eq!(Governance::counting_votes(), 0);
+        assert_ok!(Governance::undelegate(Origin::signed(2), None));
+    });
+}
+
+#[test]
+fn count_over_blocks() {
+    TestExternalities::default().execute_with(|| {
+        register_ca_and_accounts(3);
+        set_max_conclusion_weight(SIMPLE_VOTE_WEIGHT + DELEGATOR_WEIGHT);
+        let options = [[00].to_vec()].to_vec();
+        assert_ok!(Governance::create_vote(Origin::signed(10), VoteType::Simple, 5, [00].to_vec(), 0, options.clone(), VoteSettings::default()));
+        assert_ok!(Governance::create_vote(Origin::signed(10), VoteType::Lock, 5, [00].to_vec(), 0, options, VoteSettings::default()));
+        assert_ok!(Governance::cast_ballot(Origin::signed(1), 1, Ballot::Aye));
+        assert_ok!(Governance::delegate(Origin::signed(2), 1, None));
+        assert_ok!(Governance::delegate(Origin::signed(3), 1, None));
+
+        // one delegator a block. vote 2 keeps its turn behind vote 1
+        run_to_block_finalizing(7);
+        assert_eq!(Governance::votes(1).concluded, false);
+        assert_eq!(Governance::expiring_at(7), [1, 2].to_vec());
+        assert_noop!(Governance::delegate(Origin::signed(3), 2, None), "Delegations cannot change while a vote counts them.");
+
+        run_to_block_finalizing(8);
+        assert_eq!(Governance::votes(1).concluded, true);
+        assert_eq!(Governance::result_of(1).tally.totals, [3, 0, 0].to_vec());
+        assert_eq!(Governance::expiring_at(8), [2].to_vec());
+
+        // Lock votes have no delegators to count
+        run_to_block_finalizing(9);
+        assert_eq!(Governance::votes(2).concluded, true);
+        assert_eq!(Governance::count_progress_of(1), None);
+    });
+}
+
+#[test]
+fn dispatch_proposal_of_passed_vote() {
+    build_ext().execute_with(|| {
+        register_ca_and_accounts(2);
+        let cancel = |reference_index| Some(Box::new(Call::Governance(super::Call::cancel_vote(reference_index, 10))));
+        let set_balance = Some(Box::new(Call::Balances(balances::Call::set_balance(42, 1000, 0))));
+        let options = [[00].to_vec()].to_vec();
+        // votes with a proposal need a certificate and a quorum of ProposalQuorum
+        assert_noop!(Governance::create_vote(Origin::signed(10), VoteType::Simple, 5, [00].to_vec(), 0, options.clone(), VoteSettings { quorum: 2, proposal: cancel(3), ..Default::default() }), "Votes with a proposal require a certificate.");
+        assert_noop!(Governance::create_vote(Origin::signed(10), VoteType::Simple, 5, [00].to_vec(), 1, options.clone(), VoteSettings { quorum: 1, proposal: cancel(3), ..Default::default() }), "Quorum is below the minimum of votes with a proposal.");
+        assert_ok!(Governance::create_vote(Origin::signed(10), VoteType::Simple, 5, [00].to_vec(), 1, options.clone(), VoteSettings { quorum: 2, proposal: cancel(3), ..Default::default() }));
+        assert_ok!(Governance::create_vote(Origin::signed(10), VoteType::Simple, 5, [00].to_vec(), 1, options.clone(), VoteSettings { quorum: 2, proposal: set_balance, ..Default::default() }));
+        assert_ok!(Governance::create_vote(Origin::signed(10), VoteType::Simple, 5, [00].to_vec(), 0, options, VoteSettings::default()));
+        assert!(Governance::proposal_of(1).is_some());
+
+        for reference_index in 1..3 {
+            assert_ok!(Governance::cast_ballot(Origin::signed(1), reference_index, Ballot::Aye));
+            assert_ok!(Governance::cast_ballot(Origin::signed(2), reference_index, Ballot::Aye));
+        }
+
+        run_to_block(7);
+        assert_ok!(Governance::conclude_vote(Origin::signed(1), 1, 10));
+        assert_ok!(Governance::conclude_vote(Origin::signed(1), 2, 10));
+
+        // proposals are dispatched with the origin of the passed vote, which is neither root nor CancelOrigin
+        assert_eq!(Governance::outcome_of(3), None);
+        assert_eq!(Balances::free_balance(&42), 0);
+        assert!(Governance::proposal_of(1).is_none());
+        assert!(Governance::proposal_of(2).is_none());
+    });
+}
+
+#[test]
+fn delegate() {
+    TestExternalities::default().execute_with(|| {
+        register_ca_and_accounts(5);
+        let options = [[00].to_vec()].to_vec();
+        assert_ok!(Governance::create_vote(Origin::signed(10), VoteType::Simple, 5, [00].to_vec(), 0, options.clone(), VoteSettings::default()));
+        assert_ok!(Governance::create_vote(Origin::signed(10), VoteType::Simple, 5, [00].to_vec(), 0, options.clone(), VoteSettings::default()));
+        assert_ok!(Governance::create_vote(Origin::signed(10), VoteType::Lock, 5, [00].to_vec(), 0, options, VoteSettings::default()));
+
+        assert_noop!(Governance::delegate(Origin::signed(6), 1, None), "Only certified accounts can delegate.");
+        assert_noop!(Governance::delegate(Origin::signed(3), 3, None), "You cannot delegate to yourself.");
+        assert_noop!(Governance::delegate(Origin::signed(3), 1, Some(3)), "Votes of this vote_type cannot be delegated.");
+
+        // 4 -> 3 -> 1 on every vote, 5 -> 2 on vote 1 only
+        assert_ok!(Governance::delegate(Origin::signed(3), 1, None));
+        assert_ok!(Governance::delegate(Origin::signed(4), 3, None));
+        assert_ok!(Governance::delegate(Origin::signed(5), 2, Some(1)));
+        assert_eq!(Governance::delegate_of((4, None)), Some(3));
+        assert_noop!(Governance::delegate(Origin::signed(1), 4, None), "Delegation would create a cycle.");
+
+        assert_ok!(Governance::cast_ballot(Origin::signed(1), 1, Ballot::Aye));
+        assert_ok!(Governance::cast_ballot(Origin::signed(2), 1, Ballot::Nay));
+        // 3 votes directly on vote 2, so 4 follows 3 instead of 1
+        assert_ok!(Governance::cast_ballot(Origin::signed(1), 2, Ballot::Aye));
+        assert_ok!(Governance::cast_ballot(Origin::signed(3), 2, Ballot::Nay));
+
+        run_to_block(7);
+        // 3 delegators visited with up to MaxDelegationDepth delegates each
+        assert_ok!(Governance::conclude_vote(Origin::signed(1), 1, 12));
+        assert_ok!(Governance::conclude_vote(Origin::signed(1), 2, 12));
+        assert_eq!(Governance::result_of(1).tally.totals, [3, 2, 0].to_vec());
+        assert_eq!(Governance::result_of(2).tally.totals, [1, 2, 0].to_vec());
+        // delegators count as heads of their delegate's option, but cast no ballot
+        let result = Governance::result_of(1);
+        assert_eq!((result.head_counts, result.ballots, result.turnout), ([3, 2, 0].to_vec(), 2, 5));
+        assert_eq!(result.eligible, None);
+
+        // the last delegator of the scope takes the index of the removed one
+        assert_ok!(Governance::delegate(Origin::signed(2), 3, None));
+        assert_eq!(Governance::delegator_count(None), 3);
+        assert_ok!(Governance::undelegate(Origin::signed(3), None));
+        assert_noop!(Governance::undelegate(Origin::signed(3), None), "You have not delegated.");
+        assert_eq!(Governance::delegator_count(None), 2);
+        assert_eq!(<Delegators<Test>>::get((None, 0)), 2);
+        assert_ok!(Governance::undelegate(Origin::signed(2), None));
+        assert_ok!(Governance::undelegate(Origin::signed(4), None));
+        assert_eq!(Governance::delegator_count(None), 0);
+    });
+}
+
+#[test]
+fn delegation_depth() {
+    TestExternalities::default().execute_with(|| {
+        register_ca_and_accounts(5);
+        assert_ok!(Governance::create_vote(Origin::signed(10), VoteType::Simple, 5, [00].to_vec(), 0, [[00].to_vec()].to_vec(), VoteSettings::default()));
+        // 5 -> 4 -> 3 -> 2 -> 1
+        for delegator in 2..6 {
+            assert_ok!(Governance::delegate(Origin::signed(delegator), delegator - 1, None));
+        }
+        assert_ok!(Governance::cast_ballot(Origin::signed(1), 1, Ballot::Aye));
+
+        // 5 is MaxDelegationDepth + 1 delegations away from the ballot of 1
+        run_to_block(7);
+        assert_ok!(Governance::conclude_vote(Origin::signed(1), 1, 20));
+        let result = Governance::result_of(1);
+        assert_eq!(result.tally.totals, [4, 0, 0].to_vec());
+        assert_eq!((result.ballots, result.turnout), (1, 4));
+    });
+}
+
+#[test]
+fn commit_reveal() {
+    build_ext().execute_with(|| {
+        assert_ok!(Governance::create_vote(Origin::signed(10), VoteType::CommitReveal, 5, [00].to_vec(), 0, [[00].to_vec()].to_vec(), VoteSettings::default()));
+        // reveals are accepted until block 8, concluded at the end of block 9
+        assert_eq!(Governance::expiring_at(9), [1].to_vec());
+
+        let commitment = |voter: u64, ballot: Ballot, salt: &[u8]| <Test as system::Trait>::Hashing::hash_of(&(1u64, voter, ballot, salt.to_vec()));
+        assert_noop!(Governance::cast_ballot(Origin::signed(1), 1, Ballot::Aye), "This vote is not a simple vote. Use the cast function of its vote_type instead!");
+        assert_ok!(Governance::commit_ballot(Origin::signed(1), 1, commitment(1, Ballot::Nay, b"salt")));
+        // committing again replaces the commitment without another deposit
+        assert_ok!(Governance::commit_ballot(Origin::signed(1), 1, commitment(1, Ballot::Aye, b"salt")));
+        // account 2 copies the commitment of account 1
+        assert_ok!(Governance::commit_ballot(Origin::signed(2), 1, commitment(1, Ballot::Aye, b"salt")));
+        assert_eq!(Governance::committer_count(1), 2);
+        assert_eq!(Balances::reserved_balance(1), 10);
+        assert_eq!(Balances::free_balance(2), 90);
+        // nothing is published before the reveal
+        assert_eq!(Governance::ballot_of(1, 1), None);
+        assert_noop!(Governance::reveal_ballot(Origin::signed(1), 1, Ballot::Aye, b"salt".to_vec()), "Reveal period has not started yet.");
+
+        run_to_block(5);
+        assert_noop!(Governance::commit_ballot(Origin::signed(1), 1, commitment(1, Ballot::Aye, b"salt")), "This vote has already been expired.");
+        assert_noop!(Governance::reveal_ballot(Origin::signed(1), 1, Ballot::Nay, b"salt".to_vec()), "Revealed ballot doesn't match the commitment.");
+        assert_ok!(Governance::reveal_ballot(Origin::signed(1), 1, Ballot::Aye, b"salt".to_vec()));
+        // the opening of account 1 does not open the copy
+        assert_noop!(Governance::reveal_ballot(Origin::signed(2), 1, Ballot::Aye, b"salt".to_vec()), "Revealed ballot doesn't match the commitment.");
+        assert_eq!(Balances::reserved_balance(1), 0);
+        assert_eq!(Governance::ballot_of(1, 1).map(|record| record.ballot), Some(OptionBallot::Binary(Ballot::Aye)));
+
+        run_to_block(8);
+        assert_noop!(Governance::conclude_vote(Origin::signed(1), 1, 10), "This vote hasn't been expired yet.");
+
+        // account 2 never revealed and loses its deposit
+        run_to_block_finalizing(10);
+        assert_eq!(Governance::outcome_of(1), Some(Outcome::Passed));
+        assert_eq!(Governance::commitment_of((1, 2)), None);
+        assert_eq!(Governance::committer_count(1), 0);
+        assert_eq!(Balances::reserved_balance(2), 0);
+        assert_eq!(Balances::free_balance(2), 90);
+        assert_eq!(Balances::free_balance(1), 100);
+    });
+}
+
+#[test]
+fn encrypted_vote() {
+    use curve25519_dalek::{constants::RISTRETTO_BASEPOINT_POINT, scalar::Scalar};
+    build_ext().execute_with(|| {
+        assert_ok!(Governance::create_vote(Origin::signed(10), VoteType::Encrypted, 5, [00].to_vec(), 0, [[00].to_vec()].to_vec(), VoteSettings::default()));
+        // key shares on 7 + 3x: any 2 of the trustees 20, 21 and 22 decrypt
+        let commitments = [7u64, 3].iter().map(|a| (Scalar::from(*a) * RISTRETTO_BASEPOINT_POINT).compress().to_bytes()).collect::<Vec<_>>();
+        let secret = |index: u64| Scalar::from(7 + 3 * index);
+        let ballot = |voter: u64, aye: bool, seed: u64| {
+            let nonce = |n: u64| Scalar::from(seed * 10 + n).invert();
+            elgamal::encrypt_ballot(&commitments[0], aye, &(1u64, voter).encode(), nonce(1), nonce(2), nonce(3), nonce(4)).unwrap()
+        };
+
+        let (ciphertext, proof) = ballot(1, true, 1);
+        assert_noop!(Governance::cast_encrypted_ballot(Origin::signed(1), 1, ciphertext, proof.clone()), "Trustees are not set yet.");
+        assert_noop!(Governance::set_trustees(Origin::signed(1), 1, [20, 21, 22].to_vec(), commitments.clone()), "Only the creator can set trustees.");
+        assert_noop!(Governance::set_trustees(Origin::signed(10), 1, [20].to_vec(), commitments.clone()), "Threshold cannot exceed the number of trustees.");
+        assert_ok!(Governance::set_trustees(Origin::signed(10), 1, [20, 21, 22].to_vec(), commitments.clone()));
+
+        assert_ok!(Governance::cast_encrypted_ballot(Origin::signed(1), 1, ciphertext, proof.clone()));
+        // a copied ballot is bound to its voter
+        assert_noop!(Governance::cast_encrypted_ballot(Origin::signed(2), 1, ciphertext, proof), "Invalid ballot proof.");
+        let (ciphertext, proof) = ballot(2, false, 2);
+        assert_ok!(Governance::cast_encrypted_ballot(Origin::signed(2), 1, ciphertext, proof));
+        let (ciphertext, proof) = ballot(3, false, 3);
+        assert_ok!(Governance::cast_encrypted_ballot(Origin::signed(3), 1, ciphertext, proof));
+        // replaces the previous ballot in the sum
+        let (ciphertext, proof) = ballot(3, true, 4);
+        assert_ok!(Governance::cast_encrypted_ballot(Origin::signed(3), 1, ciphertext, proof));
+        assert_noop!(Governance::set_trustees(Origin::signed(10), 1, [20, 21].to_vec(), commitments.clone()), "Trustees cannot change after ballots are cast.");
+
+        let total = Governance::encrypted_total(1);
+        let (share, proof) = elgamal::decryption_share(secret(2), &total, Scalar::from(99u64)).unwrap();
+        assert_noop!(Governance::submit_decryption_share(Origin::signed(21), 1, share, proof.clone(), 10), "This vote hasn't been expired yet.");
+
+        // waits for the trustees after expiry
+        run_to_block_finalizing(7);
+        assert_eq!(Governance::votes(1).concluded, false);
+        assert_noop!(Governance::submit_decryption_share(Origin::signed(21), 1, share, proof.clone(), 2), "The vote has more voters than max_voters.");
+        assert_noop!(Governance::submit_decryption_share(Origin::signed(20), 1, share, proof.clone(), 10), "Invalid decryption share proof.");
+        assert_noop!(Governance::submit_decryption_share(Origin::signed(1), 1, share, proof.clone(), 10), "You are not a trustee of this vote.");
+        assert_ok!(Governance::submit_decryption_share(Origin::signed(21), 1, share, proof.clone(), 10));
+        assert_noop!(Governance::submit_decryption_share(Origin::signed(21), 1, share, proof, 10), "You have already submitted a share.");
+        assert_noop!(Governance::conclude_vote(Origin::signed(1), 1, 10), "Waiting for decryption shares from the trustees.");
+
+        let (share, proof) = elgamal::decryption_share(secret(3), &total, Scalar::from(98u64)).unwrap();
+        assert_ok!(Governance::submit_decryption_share(Origin::signed(22), 1, share, proof, 10));
+        assert_eq!(Governance::votes(1).concluded, true);
+        assert_eq!(Governance::result_of(1).tally.totals, [2, 1].to_vec());
+        assert_eq!(Governance::outcome_of(1), Some(Outcome::Passed));
+    });
+}
+
+#[test]
+fn scheduled_opening() {
+    build_ext().execute_with(|| {
+        System::set_block_number(1);
+        assert_noop!(Governance::create_vote(Origin::signed(10), VoteType::Simple, 5, [00].to_vec(), 0, [[00].to_vec()].to_vec(), VoteSettings { starts_at: Some(0), ..Default::default() }), "Vote cannot start in the past.");
+        // announced at block 1, open from block 3 to 7
+        assert_ok!(Governance::create_vote(Origin::signed(10), VoteType::Simple, 5, [00].to_vec(), 0, [[00].to_vec()].to_vec(), VoteSettings { starts_at: Some(3), ..Default::default() }));
+        assert_eq!(Governance::votes(1).vote_ends, 8);
+        assert_eq!(Governance::opening_at(3), [1].to_vec());
+        assert_eq!(Governance::expiring_at(9), [1].to_vec());
+
+        run_to_block(2);
+        assert_noop!(Governance::cast_ballot(Origin::signed(1), 1, Ballot::Aye), "This vote hasn't opened yet.");
+
+        run_to_block(3);
+        Governance::on_initialize(3);
+        assert_eq!(Governance::opening_at(3).len(), 0);
+        assert_ok!(Governance::cast_ballot(Origin::signed(1), 1, Ballot::Aye));
+    });
+}
+
+#[test]
+fn creation_deposit() {
+    build_ext().execute_with(|| {
+        let options = [[00].to_vec()].to_vec();
+        // 88 bytes: 83 for the Vote, 1 for data, 3 for options, 1 for no proposal
+        set_creation_deposit(5, 1);
+        assert_ok!(Governance::create_vote(Origin::signed(10), VoteType::Simple, 5, [00].to_vec(), 0, options.clone(), VoteSettings::default()));
+        assert_eq!(Governance::creation_deposit_of(1), Some((10, 93)));
+        assert_eq!(Balances::reserved_balance(10), 93);
+        assert_noop!(Governance::create_vote(Origin::signed(10), VoteType::Simple, 5, [00].to_vec(), 0, options.clone(), VoteSettings::default()), "Not enough free balance for the creation deposit.");
+
+        // only SpamOrigin flags spam
+        assert!(Governance::flag_spam(Origin::signed(1), 1).is_err());
+        assert_ok!(Governance::flag_spam(Origin::ROOT, 1));
+        assert_noop!(Governance::flag_spam(Origin::ROOT, 1), "This vote has no deposit to slash.");
+        assert_eq!(Balances::reserved_balance(10), 0);
+        assert_eq!(Balances::free_balance(10), 7);
+
+        // returned when the vote meets quorum, slashed when it doesn't
+        set_creation_deposit(3, 0);
+        assert_ok!(Governance::create_vote(Origin::signed(10), VoteType::Simple, 5, [00].to_vec(), 0, options.clone(), VoteSettings { quorum: 1, ..Default::default() }));
+        assert_ok!(Governance::create_vote(Origin::signed(10), VoteType::Simple, 5, [00].to_vec(), 0, options.clone(), VoteSettings { quorum: 1, ..Default::default() }));
+        assert_ok!(Governance::cast_ballot(Origin::signed(1), 2, Ballot::Nay));
+        assert_eq!(Balances::reserved_balance(10), 6);
+
+        run_to_block(7);
+        assert_ok!(Governance::conclude_vote(Origin::signed(1), 2, 10));
+        assert_ok!(Governance::conclude_vote(Origin::signed(1), 3, 10));
+        assert_eq!(Governance::outcome_of(3), Some(Outcome::QuorumNotMet));
+        assert_eq!(Governance::creation_deposit_of(2), None);
+        assert_eq!(Balances::reserved_balance(10), 0);
+        assert_eq!(Balances::free_balance(10), 4);
+
+        // RankedChoice votes pay for their runoff rounds too
+        // 160 bytes: 83 for the Vote, 1 for data, 5 for options, 1 for no proposal, and 2 rounds of 35
+        set_creation_deposit(0, 1);
+        Balances::make_free_balance_be(&2, 1000);
+        let ranked = [[00].to_vec(), [01].to_vec()].to_vec();
+        assert_ok!(Governance::create_vote(Origin::signed(2), VoteType::RankedChoice, 5, [00].to_vec(), 0, ranked, VoteSettings::default()));
+        assert_eq!(Governance::creation_deposit_of(4), Some((2, 160)));
+    });
+}
+
+#[test]
+fn reap_vote() {
+    build_ext().execute_with(|| {
+        set_free_balance();
+        set_creation_deposit(10, 0);
+        set_reap_reward(4);
+        assert_ok!(Governance::create_vote(Origin::signed(10), VoteType::Lock, 5, [00].to_vec(), 0, [[00].to_vec()].to_vec(), VoteSettings::default()));
+        assert_eq!(Governance::creation_deposit_of(1), Some((10, 6)));
+        assert_eq!(Governance::reap_reward_of(1), Some((10, 4)));
+        // 2 periods past vote_ends: x3
+        assert_ok!(Governance::cast_lockvote(Origin::signed(1), 1, Ballot::Aye, 10, 25));
+        assert_ok!(Governance::cast_lockvote(Origin::signed(2), 1, Ballot::Nay, 20, 5));
+        assert_noop!(Governance::reap_vote(Origin::signed(2), 1, 10), "This vote has not concluded yet.");
+
+        run_to_block(6);
+        assert_ok!(Governance::conclude_vote(Origin::signed(1), 1, 10));
+        assert_eq!(Balances::reserved_balance(10), 4);
+        assert_noop!(Governance::reap_vote(Origin::signed(2), 1, 10), "The retention period of this vote is not over yet.");
+        run_to_block(16);
+        assert_noop!(Governance::reap_vote(Origin::signed(2), 1, 10), "Locks of this vote are still active.");
+
+        // a page of one voter. the reward waits for the last page
+        run_to_block(26);
+        assert_ok!(Governance::reap_vote(Origin::signed(3), 1, 1));
+        assert_eq!(Governance::reap_progress_of(1).map(|(reaped, _)| reaped), Some(1));
+        assert_eq!((Governance::ballot_of(1, 1), Governance::ballot_of(1, 2).is_some()), (None, true));
+        assert_eq!(Governance::archive_of(1), None);
+        assert_eq!(Balances::reserved_balance(10), 4);
+        assert_ok!(Governance::reap_vote(Origin::signed(2), 1, 10));
+        assert_noop!(Governance::reap_vote(Origin::signed(2), 1, 10), "This vote has already been reaped.");
+        assert_eq!(Governance::reap_progress_of(1), None);
+        assert_eq!(Balances::reserved_balance(10), 0);
+        assert_eq!(Balances::free_balance(2), 104);
+        assert_eq!(Governance::reap_reward_of(1), None);
+        // locks released, per-voter data cleared
+        assert_eq!(Balances::locks(&1).len(), 0);
+        assert_eq!(Governance::locked_votes_of(2).len(), 0);
+        assert_eq!(Governance::ballot_of(1, 1), None);
+        assert_eq!(Governance::voter_count(1), 0);
+        assert_eq!(Governance::option_total((1, 0)), 0);
+        assert_eq!(Governance::data(1).len(), 0);
+
+        // the result stays, with the root of the ballots
+        assert!(Governance::votes(1).concluded);
+        assert_eq!(Governance::result_of(1).tally.totals, [30, 20, 0].to_vec());
+        type Hashing = <Test as system::Trait>::Hashing;
+        let aye = Hashing::hash_of(&(1u64, BallotRecord { ballot: OptionBallot::Binary(Ballot::Aye), weight: 30 }));
+        let nay = Hashing::hash_of(&(2u64, BallotRecord { ballot: OptionBallot::Binary(Ballot::Nay), weight: 20 }));
+        assert_eq!(Governance::archive_of(1), Some(VoteArchive { ballot_root: Hashing::hash_of(&(aye, nay)), ballots: 2 }));
+    });
+}
+
+#[test]
+fn reap_vote_delegations() {
+    TestExternalities::default().execute_with(|| {
+        register_ca_and_accounts(4);
+        assert_ok!(Governance::create_vote(Origin::signed(10), VoteType::Simple, 5, [00].to_vec(), 0, [[00].to_vec()].to_vec(), VoteSettings::default()));
+        // 4 never votes
+        assert_ok!(Governance::delegate(Origin::signed(2), 1, Some(1)));
+        assert_ok!(Governance::delegate(Origin::signed(3), 4, Some(1)));
+        assert_ok!(Governance::cast_ballot(Origin::signed(1), 1, Ballot::Aye));
+        run_to_block(7);
+        assert_ok!(Governance::conclude_vote(Origin::signed(1), 1, 8));
+        assert_eq!(Governance::result_of(1).turnout, 2);
+
+        // delegations for the vote take from the same max_accounts as voters
+        run_to_block(16);
+        assert_ok!(Governance::reap_vote(Origin::signed(1), 1, 2));
+        assert_eq!(Governance::reap_progress_of(1).map(|(reaped, _)| reaped), Some(1));
+        assert_eq!(Governance::delegate_of((3, Some(1))), None);
+        assert_eq!(Governance::delegator_count(Some(1)), 1);
+        assert_eq!(Governance::archive_of(1), None);
+        assert_ok!(Governance::reap_vote(Origin::signed(1), 1, 1));
+        assert_eq!(Governance::delegate_of((2, Some(1))), None);
+        assert_eq!(Governance::delegator_count(Some(1)), 0);
+        assert_eq!(<DelegatorIndex<Test>>::exists((2, Some(1))), false);
+        assert_eq!(Governance::archive_of(1).map(|archive| archive.ballots), Some(1));
+    });
+}
+
+#[test]
+fn reap_spam_vote() {
+    build_ext().execute_with(|| {
+        set_creation_deposit(10, 0);
+        set_reap_reward(4);
+        assert_ok!(Governance::create_vote(Origin::signed(10), VoteType::Simple, 5, [00].to_vec(), 0, [[00].to_vec()].to_vec(), VoteSettings::default()));
+        // flag_spam slashes the deposit, the ReapReward share stays for the reaper
+        assert_ok!(Governance::flag_spam(Origin::ROOT, 1));
+        assert_eq!(Governance::creation_deposit_of(1), None);
+        assert_eq!(Governance::reap_reward_of(1), Some((10, 4)));
+        assert_eq!(Balances::reserved_balance(10), 4);
+
+        run_to_block(6);
+        assert_ok!(Governance::conclude_vote(Origin::signed(1), 1, 10));
+        run_to_block(16);
+        let free = Balances::free_balance(3);
+        assert_ok!(Governance::reap_vote(Origin::signed(3), 1, 0));
+        assert_eq!(Balances::free_balance(3), free + 4);
+        assert_eq!(Balances::reserved_balance(10), 0);
+        assert_eq!(Governance::archive_of(1), Some(VoteArchive { ballot_root: H256::default(), ballots: 0 }));
+    });
+}
+
+#[test]
+fn cancel_vote() {
+    build_ext().execute_with(|| {
+        set_free_balance();
+        set_creation_deposit(3, 0);
+        let options = [[00].to_vec()].to_vec();
+        assert_ok!(Governance::create_vote(Origin::signed(10), VoteType::Simple, 5, [00].to_vec(), 0, options.clone(), VoteSettings::default()));
+        assert_ok!(Governance::create_vote(Origin::signed(10), VoteType::Lock, 5, [00].to_vec(), 0, options.clone(), VoteSettings::default()));
+        assert_ok!(Governance::create_vote(Origin::signed(10), VoteType::Simple, 5, [00].to_vec(), 0, options.clone(), VoteSettings { starts_at: Some(3), ..Default::default() }));
+        assert_ok!(Governance::create_vote(Origin::signed(10), VoteType::Simple, 5, [00].to_vec(), 0, options, VoteSettings::default()));
+        assert_eq!(Balances::reserved_balance(10), 12);
+
+        // the creator cancels while no ballot is cast. the deposit of an opened vote is slashed
+        assert_noop!(Governance::cancel_vote(Origin::signed(1), 1, 0), "Only the creator can cancel this vote.");
+        assert_ok!(Governance::cancel_vote(Origin::signed(10), 1, 0));
+        assert_eq!(Governance::outcome_of(1), Some(Outcome::Cancelled));
+        assert_eq!(Balances::reserved_balance(10), 9);
+        assert_noop!(Governance::cast_ballot(Origin::signed(1), 1, Ballot::Aye), "This vote has been cancelled.");
+        assert_noop!(Governance::cancel_vote(Origin::signed(10), 1, 0), "This vote has already concluded.");
+        // and returned before the vote opens
+        assert_ok!(Governance::cancel_vote(Origin::signed(10), 3, 0));
+        assert_eq!((Balances::reserved_balance(10), Balances::free_balance(10)), (6, 91));
+
+        assert_ok!(Governance::cast_lockvote(Origin::signed(1), 2, Ballot::Aye, 10, 10));
+        assert_ok!(Governance::cast_lockvote(Origin::signed(2), 2, Ballot::Nay, 20, 10));
+        assert_noop!(Governance::cancel_vote(Origin::signed(10), 2, 0), "Ballots have already been cast.");
+
+        // root cancels at any time, unlocking max_accounts lockvotes a call. anyone unlocks the rest
+        assert_ok!(Governance::cancel_vote(Origin::ROOT, 2, 1));
+        assert_eq!(Governance::votes(2).concluded, true);
+        assert_eq!(Balances::reserved_balance(10), 3);
+        assert_eq!((Balances::locks(&1).len(), Balances::locks(&2).len()), (0, 1));
+        assert_eq!(Governance::cancel_progress_of(2), Some(1));
+        assert_ok!(Governance::cancel_vote(Origin::signed(3), 2, 1));
+        assert_eq!(Balances::locks(&2).len(), 0);
+        assert_eq!(<LockBalance<Test>>::exists((2, 2)), false);
+        assert_eq!(Governance::cancel_progress_of(2), None);
+        assert_noop!(Governance::cancel_vote(Origin::ROOT, 2, 1), "This vote has already concluded.");
+
+        run_to_block(7);
+        assert_noop!(Governance::cancel_vote(Origin::signed(10), 4, 0), "This vote has already been expired.");
+        assert_noop!(Governance::conclude_vote(Origin::signed(1), 2, 10), "This vote has already concluded.");
+        assert_eq!(Governance::outcome_of(2), Some(Outcome::Cancelled));
+    });
+}
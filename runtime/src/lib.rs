@@ -65,7 +65,6 @@ pub type DigestItem = generic::DigestItem<Hash>;
 // mod template;
 mod governance;
 mod certificate;
-mod rsa;
 
 /// Opaque types. These are used by the CLI to instantiate machinery that don't need to know
 /// the specifics of the runtime. They can then be made to be agnostic over specific formats
@@ -98,8 +97,8 @@ pub const VERSION: RuntimeVersion = RuntimeVersion {
 	spec_name: create_runtime_str!("m-governance"),
 	impl_name: create_runtime_str!("m-governance"),
 	authoring_version: 3,
-	spec_version: 4,
-	impl_version: 4,
+	spec_version: 5,
+	impl_version: 5,
 	apis: RUNTIME_API_VERSIONS,
 };
 
@@ -244,9 +243,44 @@ impl certificate::Trait for Runtime {
 	type Event = Event;
 }
 
+parameter_types! {
+	pub const MaxConclusionWeight: Weight = 250_000;
+	pub const RevealPeriod: BlockNumber = 1 * HOURS;
+	pub const RevealDeposit: Balance = 1_000;
+	pub const CreationDepositBase: Balance = 1_000;
+	pub const CreationDepositPerByte: Balance = 10;
+	pub const LockPeriod: BlockNumber = 1 * DAYS;
+	pub const Convictions: &'static [(u32, u32)] = &[(0, 1), (1, 2), (2, 3), (4, 4), (8, 5), (16, 6)];
+	pub const RetentionPeriod: BlockNumber = 30 * DAYS;
+	pub const ReapReward: Balance = 100;
+	pub const ProposalQuorum: u64 = 10;
+	pub const MaxProposalWeight: Weight = 100_000;
+	pub const MaxDelegationDepth: u32 = 8;
+}
+
 impl governance::Trait for Runtime {
 	type Event = Event;
 	type Currency = Balances;
+	type SimpleTally = governance::tally::SimpleMajority;
+	type LockTally = governance::tally::LockWeighted;
+	type MaxConclusionWeight = MaxConclusionWeight;
+	type ProposalOrigin = Origin;
+	type Proposal = Call;
+	type ProposalQuorum = ProposalQuorum;
+	type MaxProposalWeight = MaxProposalWeight;
+	type RevealPeriod = RevealPeriod;
+	type RevealDeposit = RevealDeposit;
+	type Slash = ();
+	type CreationDepositBase = CreationDepositBase;
+	type CreationDepositPerByte = CreationDepositPerByte;
+	type SpamOrigin = system::EnsureRoot<AccountId>;
+	type CancelOrigin = system::EnsureRoot<AccountId>;
+	type LockPeriod = LockPeriod;
+	type Convictions = Convictions;
+	type Treasury = ();
+	type RetentionPeriod = RetentionPeriod;
+	type ReapReward = ReapReward;
+	type MaxDelegationDepth = MaxDelegationDepth;
 }
 
 
@@ -265,9 +299,9 @@ construct_runtime!(
 		TransactionPayment: transaction_payment::{Module, Storage},
 		Sudo: sudo,
 		// Used for the module template in `./template.rs`
-		CertificateModule: certificate::{Module, Call, Storage, Event<T>},
+		CertificateModule: certificate::{Module, Call, Storage, Event<T>, Config},
 		RandomnessCollectiveFlip: randomness_collective_flip::{Module, Call, Storage},
-		GovernanceModule: governance::{Module, Call, Storage, Event<T>},
+		GovernanceModule: governance::{Module, Call, Storage, Event<T>, Config, Origin},
 	}
 );
 
@@ -1,7 +1,7 @@
 use primitives::{Pair, Public};
 use m_governance_runtime::{
 	AccountId, AuraConfig, BalancesConfig, GenesisConfig, GrandpaConfig,
-	SudoConfig, IndicesConfig, SystemConfig, WASM_BINARY, 
+	SudoConfig, IndicesConfig, SystemConfig, CertificateModuleConfig, GovernanceModuleConfig, WASM_BINARY, 
 };
 use aura_primitives::sr25519::{AuthorityId as AuraId};
 use grandpa_primitives::{AuthorityId as GrandpaId};
@@ -129,5 +129,7 @@ fn testnet_genesis(initial_authorities: Vec<(AuraId, GrandpaId)>,
 		grandpa: Some(GrandpaConfig {
 			authorities: initial_authorities.iter().map(|x| (x.1.clone(), 1)).collect(),
 		}),
+		certificate_module: Some(CertificateModuleConfig::default()),
+		governance_module: Some(GovernanceModuleConfig::default()),
 	}
 }